@@ -0,0 +1,272 @@
+//! # bevy-pigeon-derive
+//!
+//! Derive macros for `bevy-pigeon`.
+//!
+//! You should not depend on this crate directly. Instead, enable the `derive` feature of
+//! `bevy-pigeon`, which re-exports these macros.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, LitFloat, Meta,
+    NestedMeta, Path, Result, Type,
+};
+
+/// Generates a network-able message type for a component, along with the [`From`] conversions
+/// both ways.
+///
+/// The generated type is named `Net` followed by the name of the type, unless a name is given with
+/// `#[net(name = "...")]`. It implements `Clone`, `Debug`, `PartialEq`, `Serialize` and
+/// `Deserialize`, so it can be used as the message type `M` in `sync_comp::<T, M>`. The types that
+/// the fields are sent as must implement these too.
+///
+/// ### Field attributes
+/// - `#[net(skip)]`: The field is not sent. It is set to its [`Default`] value when converting
+///   back.
+/// - `#[net(quantize = 0.01)]`: The field (an `f32` or `f64`) is sent as an `i32`, in steps of the
+///   given size. A suffix on the step (like `0.01f32`) is ignored.
+/// - `#[net(with = "NetType")]`: The field is sent as `NetType`, using the [`From`] conversions
+///   between the field's type and `NetType`.
+///
+/// ### Example
+/// ```ignore
+/// #[derive(Component, Clone, NetMessage)]
+/// struct Health {
+///     #[net(quantize = 0.1)]
+///     current: f32,
+///     max: f32,
+///     #[net(skip)]
+///     regen_timer: Timer,
+/// }
+///
+/// app.sync_comp::<Health, NetHealth>(&mut table, Transport::UDP);
+/// ```
+#[proc_macro_derive(NetMessage, attributes(net))]
+pub fn derive_net_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is sent.
+enum FieldKind {
+    /// Sent as is.
+    Plain,
+    /// Not sent.
+    Skip,
+    /// Sent as an `i32`, in steps of the given size. The literal is always an `f64`.
+    Quantize(LitFloat),
+    /// Sent as the given type.
+    With(Path),
+}
+
+/// A field of the component.
+struct NetField {
+    /// The field's name, or index for tuple structs.
+    member: TokenStream2,
+    ty: Type,
+    kind: FieldKind,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "NetMessage can not be derived for generic types",
+        ));
+    }
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "NetMessage can only be derived for structs",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let net_ident = net_name(&input.attrs)?.unwrap_or_else(|| format_ident!("Net{}", ident));
+
+    let mut fields = vec![];
+    for (i, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(i);
+                quote!(#index)
+            }
+        };
+        fields.push(NetField {
+            member,
+            ty: field.ty.clone(),
+            kind: field_kind(&field.attrs)?,
+        });
+    }
+    let sent: Vec<&NetField> = fields
+        .iter()
+        .filter(|f| !matches!(f.kind, FieldKind::Skip))
+        .collect();
+
+    // The field definitions of the message type.
+    let defs = sent.iter().map(|f| {
+        let ty = match &f.kind {
+            FieldKind::Quantize(_) => quote!(i32),
+            FieldKind::With(path) => quote!(#path),
+            _ => {
+                let ty = &f.ty;
+                quote!(#ty)
+            }
+        };
+        match &data.fields {
+            Fields::Named(_) => {
+                let member = &f.member;
+                quote!(pub #member: #ty)
+            }
+            _ => quote!(pub #ty),
+        }
+    });
+
+    // The conversions from the component to the message type.
+    let to_net = sent.iter().enumerate().map(|(i, f)| {
+        let member = &f.member;
+        let value = match &f.kind {
+            FieldKind::Quantize(step) => quote!((o.#member as f64 / #step).round() as i32),
+            FieldKind::With(_) => quote!(o.#member.into()),
+            _ => quote!(o.#member),
+        };
+        match &data.fields {
+            Fields::Named(_) => quote!(#member: #value),
+            _ => {
+                let index = syn::Index::from(i);
+                quote!(#index: #value)
+            }
+        }
+    });
+
+    // The conversions from the message type to the component.
+    let mut net_index = 0;
+    let from_net = fields.iter().map(|f| {
+        let member = &f.member;
+        let ty = &f.ty;
+        let net_member = match &data.fields {
+            Fields::Named(_) => member.clone(),
+            _ => {
+                let index = syn::Index::from(net_index);
+                quote!(#index)
+            }
+        };
+        let value = match &f.kind {
+            FieldKind::Skip => quote!(::core::default::Default::default()),
+            FieldKind::Quantize(step) => quote!((o.#net_member as f64 * #step) as #ty),
+            FieldKind::With(_) => quote!(o.#net_member.into()),
+            FieldKind::Plain => quote!(o.#net_member),
+        };
+        if !matches!(f.kind, FieldKind::Skip) {
+            net_index += 1;
+        }
+        quote!(#member: #value)
+    });
+    let from_net: Vec<TokenStream2> = from_net.collect();
+
+    let def = match &data.fields {
+        Fields::Named(_) => quote!(#vis struct #net_ident { #(#defs,)* }),
+        Fields::Unnamed(_) => quote!(#vis struct #net_ident(#(#defs,)*);),
+        Fields::Unit => quote!(#vis struct #net_ident;),
+    };
+    let doc = format!("The network-able version of [`{}`].", ident);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(
+            Clone,
+            Debug,
+            PartialEq,
+            ::bevy_pigeon::__private::serde::Serialize,
+            ::bevy_pigeon::__private::serde::Deserialize,
+        )]
+        #[serde(crate = "::bevy_pigeon::__private::serde")]
+        #def
+
+        impl ::core::convert::From<#ident> for #net_ident {
+            #[allow(unused_variables)]
+            fn from(o: #ident) -> Self {
+                #net_ident { #(#to_net,)* }
+            }
+        }
+
+        impl ::core::convert::From<#net_ident> for #ident {
+            #[allow(unused_variables)]
+            fn from(o: #net_ident) -> Self {
+                #ident { #(#from_net,)* }
+            }
+        }
+    })
+}
+
+/// Gets the `#[net(...)]` attributes.
+fn net_metas(attrs: &[Attribute]) -> Result<Vec<NestedMeta>> {
+    let mut metas = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("net")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            meta => return Err(Error::new(meta.span(), "expected `#[net(...)]`")),
+        }
+    }
+    Ok(metas)
+}
+
+/// Gets the name given with `#[net(name = "...")]` on the type.
+fn net_name(attrs: &[Attribute]) -> Result<Option<Ident>> {
+    let mut name = None;
+    for meta in net_metas(attrs)? {
+        match meta {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => match nv.lit {
+                Lit::Str(s) => name = Some(Ident::new(&s.value(), s.span())),
+                lit => return Err(Error::new(lit.span(), "expected a string")),
+            },
+            meta => return Err(Error::new(meta.span(), "unknown attribute")),
+        }
+    }
+    Ok(name)
+}
+
+/// Gets how a field is sent from its `#[net(...)]` attributes.
+fn field_kind(attrs: &[Attribute]) -> Result<FieldKind> {
+    let mut kind = FieldKind::Plain;
+    for meta in net_metas(attrs)? {
+        if !matches!(kind, FieldKind::Plain) {
+            return Err(Error::new(
+                meta.span(),
+                "only one of `skip`, `quantize` and `with` can be used on a field",
+            ));
+        }
+        kind = match meta {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => FieldKind::Skip,
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("quantize") => match nv.lit {
+                // The arithmetic is done in `f64`, so a suffix like `f32` would not type-check.
+                Lit::Float(step) => FieldKind::Quantize(LitFloat::new(
+                    &format!("{}f64", step.base10_digits()),
+                    step.span(),
+                )),
+                lit => return Err(Error::new(lit.span(), "expected a float, like `0.01`")),
+            },
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("with") => match nv.lit {
+                Lit::Str(s) => FieldKind::With(s.parse()?),
+                lit => {
+                    return Err(Error::new(
+                        lit.span(),
+                        "expected a type, like `\"NetColor\"`",
+                    ))
+                }
+            },
+            meta => return Err(Error::new(meta.span(), "unknown attribute")),
+        };
+    }
+    Ok(kind)
+}
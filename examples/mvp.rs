@@ -9,7 +9,7 @@ use crate::shared::*;
 use bevy::prelude::*;
 use bevy_pigeon::sync::{NetComp, NetEntity};
 use bevy_pigeon::types::NetTransform;
-use bevy_pigeon::{AppExt, ClientPlugin, ServerPlugin};
+use bevy_pigeon::{AppExt, ClientDisconnected, ClientPlugin, ConnectionPolicy, ServerPlugin};
 use carrier_pigeon::net::Config;
 use carrier_pigeon::{Client, Server, Transport};
 use std::f32::consts::PI;
@@ -44,6 +44,10 @@ fn main() {
         )
         .unwrap();
         app.insert_resource(server);
+        // Accept every connection.
+        app.insert_resource(ConnectionPolicy::<Connection, Response>::accept_all(
+            Response::Accepted,
+        ));
     }
     if is_client {
         let pending_client = Client::new(
@@ -60,10 +64,10 @@ fn main() {
     }
 
     app.add_plugins(DefaultPlugins)
-        .add_plugin(ClientPlugin)
-        .add_plugin(ServerPlugin)
+        .add_plugin(ClientPlugin::<Response>::default())
+        .add_plugin(ServerPlugin::<Connection, Response>::default())
         .add_startup_system(setup)
-        .add_system(handle_discon_con)
+        .add_system(log_disconnects)
         .add_system(spin)
         .run();
 }
@@ -116,12 +120,12 @@ fn spin(server: Option<Res<Server>>, mut q_spin: Query<&mut Transform, With<Spin
     }
 }
 
-/// Handles new connections and disconnections.
-fn handle_discon_con(server: Option<ResMut<Server>>) {
-    if let Some(mut server) = server {
-        server.handle_new_cons(|cid, _c: Connection| (true, Response::Accepted(cid)));
-        server.handle_disconnects(|cid, status| {
-            info!("Client {cid} disconnected with status: {status}");
-        });
+/// Logs disconnections.
+fn log_disconnects(mut er: EventReader<ClientDisconnected>) {
+    for discon in er.iter() {
+        info!(
+            "Client {} disconnected with status: {}",
+            discon.cid, discon.status
+        );
     }
 }
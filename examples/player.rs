@@ -10,7 +10,7 @@ use crate::menu::MenuPlugin;
 use crate::shared::*;
 use bevy::prelude::*;
 use bevy_pigeon::types::NetTransform;
-use bevy_pigeon::AppExt;
+use bevy_pigeon::{AppExt, ClientPlugin, NetStatePlugin, ServerPlugin};
 use carrier_pigeon::{CId, Transport};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -34,15 +34,11 @@ enum GameState {
 #[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 struct NewPlayer(CId);
 
-#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
-struct DelPlayer(CId);
-
 fn main() {
     let mut app = App::new();
     let mut table = get_table();
 
     table.register::<NewPlayer>(Transport::TCP).unwrap();
-    table.register::<DelPlayer>(Transport::TCP).unwrap();
 
     // Get IP addr
     let ip: SocketAddr = std::env::args()
@@ -57,13 +53,20 @@ fn main() {
 
     // Tell bevy-pigeon to sync the Transform component using the NetTransform message type.
     app.sync_comp::<Transform, NetTransform>(&mut table, Transport::UDP);
+    // Despawn a player's entities when they disconnect.
+    app.despawn_client_entities(&mut table);
 
     let parts = table.build::<Connection, Response, Disconnect>().unwrap();
     app.insert_resource(parts);
 
-    // Not adding client/server plugins in favor of custom systems.
     app.add_state(GameState::Menu)
         .add_plugins(DefaultPlugins)
+        .add_plugin(
+            ClientPlugin::<Response>::default()
+                .with_accept(|response| matches!(response, Response::Accepted(_))),
+        )
+        .add_plugin(ServerPlugin::<Connection, Response>::default())
+        .add_plugin(NetStatePlugin)
         .add_startup_system(setup)
         .add_plugin(MenuPlugin)
         .add_plugin(ConnectingPlugin)
@@ -96,8 +99,9 @@ fn clean_up<T: Component>(mut commands: Commands, q_menu: Query<Entity, With<T>>
 mod menu {
     use crate::connecting::MyCId;
     use crate::GameState::Menu;
-    use crate::{clean_up, Connection, GameState, MyConfig, SystemSet};
+    use crate::{clean_up, Connection, GameState, MyConfig, RejectReason, Response, SystemSet};
     use bevy::prelude::*;
+    use bevy_pigeon::{ConnectionPolicy, Host};
     use carrier_pigeon::net::Config;
     use carrier_pigeon::{Client, MsgTableParts, Server};
 
@@ -135,19 +139,17 @@ mod menu {
                         let server = Server::new(conf.ip, (*parts).clone(), Config::default())
                             .expect("Failed to start a server.");
                         commands.insert_resource(server);
+                        commands.insert_resource(connection_policy(conf.pass.clone()));
                     }
                     MenuButton::Host => {
                         let server = Server::new(conf.ip, (*parts).clone(), Config::default())
                             .expect("Failed to start a server.");
                         commands.insert_resource(server);
-                        let client = Client::new(
-                            conf.ip,
-                            (*parts).clone(),
-                            Config::default(),
-                            Connection::new(conf.user.clone(), conf.pass.clone()),
-                        );
-                        commands.insert_resource(client.option());
-                        commands.insert_resource(MyCId(1));
+                        commands.insert_resource(connection_policy(conf.pass.clone()));
+                        // The host plays as a local client, without connecting to itself.
+                        let host = Host::default();
+                        commands.insert_resource(host);
+                        commands.insert_resource(MyCId(host.cid));
                     }
                     MenuButton::Client => {
                         let client = Client::new(
@@ -164,6 +166,18 @@ mod menu {
         }
     }
 
+    /// Validates the password, and allows at most 3 players.
+    fn connection_policy(pass: String) -> ConnectionPolicy<Connection, Response> {
+        ConnectionPolicy::new(move |cid, con: &Connection| {
+            if con.pass != pass {
+                (false, Response::Rejected(RejectReason::IncorrectPassword))
+            } else {
+                (true, Response::Accepted(cid))
+            }
+        })
+        .with_max_connections(3, Response::Rejected(RejectReason::MaxPlayersReached))
+    }
+
     fn setup_menu(mut commands: Commands, assets: Res<AssetServer>) {
         println!("Setting up");
         let font = assets.load("FiraMono-Medium.ttf");
@@ -272,7 +286,8 @@ mod connecting {
     use crate::GameState::Connecting;
     use crate::{clean_up, GameState, Response};
     use bevy::prelude::*;
-    use carrier_pigeon::{CId, OptionPendingClient, Server};
+    use bevy_pigeon::{ConnectTimedOut, ConnectedToServer, ConnectionFailed, ConnectionRejected};
+    use carrier_pigeon::{CId, Server};
 
     #[derive(Resource, Copy, Clone, Eq, PartialEq, Debug, Hash)]
     pub struct MyCId(pub CId);
@@ -295,7 +310,10 @@ mod connecting {
     fn check_connecting(
         mut commands: Commands,
         server: Option<Res<Server>>,
-        client: Option<ResMut<OptionPendingClient>>,
+        mut er_connected: EventReader<ConnectedToServer<Response>>,
+        mut er_rejected: EventReader<ConnectionRejected<Response>>,
+        mut er_failed: EventReader<ConnectionFailed>,
+        mut er_timed_out: EventReader<ConnectTimedOut>,
         mut game_state: ResMut<State<GameState>>,
     ) {
         if server.is_some() {
@@ -303,26 +321,31 @@ mod connecting {
             let _ = game_state.set(GameState::Game);
             return;
         }
-        let mut client = client.unwrap();
-
-        if client.done().unwrap() {
-            let con_result = client.take::<Response>().unwrap();
-            let (client, response) = con_result.expect("IO Error occurred while connecting.");
-            match response {
-                Response::Accepted(cid) => {
-                    println!("Connection successful. Our CId {cid}");
-                    commands.insert_resource(client);
-                    commands.insert_resource(MyCId(cid));
-                    let _ = game_state.set(GameState::Game);
-                    commands.remove_resource::<OptionPendingClient>();
-                }
-                Response::Rejected(reason) => {
-                    println!("Connection rejected for reason: {:?}", reason);
-                    let _ = game_state.set(GameState::Menu);
-                    commands.remove_resource::<OptionPendingClient>();
-                }
+
+        for connected in er_connected.iter() {
+            if let Response::Accepted(cid) = connected.response {
+                println!("Connection successful. Our CId {cid}");
+                commands.insert_resource(MyCId(cid));
+                let _ = game_state.set(GameState::Game);
             }
         }
+
+        for rejected in er_rejected.iter() {
+            if let Response::Rejected(reason) = rejected.response {
+                println!("Connection rejected for reason: {:?}", reason);
+            }
+            let _ = game_state.set(GameState::Menu);
+        }
+
+        for failed in er_failed.iter() {
+            println!("IO Error occurred while connecting: {}", failed.error);
+            let _ = game_state.set(GameState::Menu);
+        }
+
+        for _ in er_timed_out.iter() {
+            println!("Connection timed out.");
+            let _ = game_state.set(GameState::Menu);
+        }
     }
 
     fn setup(mut commands: Commands, assets: Res<AssetServer>) {
@@ -371,15 +394,12 @@ mod connecting {
 mod game {
     use crate::connecting::MyCId;
     use crate::GameState::Game;
-    use crate::{
-        clean_up, Connection, DelPlayer, MyConfig, NewPlayer, RejectReason, Response, SystemSet,
-    };
+    use crate::{clean_up, Connection, MyConfig, NewPlayer, SystemSet};
     use bevy::prelude::*;
     use bevy::utils::HashMap;
-    use bevy_pigeon::app::{client_tick, server_tick};
     use bevy_pigeon::sync::{CNetDir, NetComp, NetEntity, SNetDir};
     use bevy_pigeon::types::NetTransform;
-    use bevy_pigeon::{NetLabel, SyncC};
+    use bevy_pigeon::{ClientConnected, ClientDisconnected, ClientEntities, Host, NetLabel, SyncC};
     use carrier_pigeon::net::CIdSpec;
     use carrier_pigeon::net::CIdSpec::{Except, Only};
     use carrier_pigeon::{CId, Client, Server};
@@ -411,11 +431,8 @@ mod game {
                 .add_system_set(SystemSet::on_enter(Game).with_system(setup_game))
                 .add_system_set(
                     SystemSet::on_update(Game)
-                        // Only tick client and server when game is running.
-                        .with_system(client_tick.label(NetLabel))
-                        .with_system(server_tick.label(NetLabel))
-                        .with_system(handle_cons.after(NetLabel))
-                        .with_system(add_del_players.after(NetLabel))
+                        .with_system(handle_cons.after(NetLabel::Recv))
+                        .with_system(add_players.after(NetLabel::Recv))
                         .with_system(move_player),
                 )
                 .add_system_set(SystemSet::on_exit(Game).with_system(clean_up::<GameItem>));
@@ -424,6 +441,9 @@ mod game {
 
     fn setup_game(
         my_cid: Option<Res<MyCId>>,
+        host: Option<Res<Host>>,
+        conf: Res<MyConfig>,
+        mut players: ResMut<Players>,
         mut commands: Commands,
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<StandardMaterial>>,
@@ -443,27 +463,21 @@ mod game {
             println!("setup");
             spawn_player(cid.0, true, &mut commands, &mut *meshes, &mut *materials);
         }
+
+        // The host is a player too.
+        if let Some(host) = host {
+            players.0.insert(host.cid, conf.user.clone());
+        }
     }
 
-    fn add_del_players(
+    fn add_players(
         mut commands: Commands,
         client: Option<Res<Client>>,
-        q_player: Query<(Entity, &NetEntity), With<Player>>,
         // For spawning player
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<StandardMaterial>>,
     ) {
         if let Some(client) = client {
-            for msg in client.recv::<DelPlayer>() {
-                if let Some((entity, _net_e)) = q_player
-                    .iter()
-                    .filter(|(_e, net_e)| net_e.id == msg.0 as u64)
-                    .next()
-                {
-                    commands.entity(entity).despawn_recursive();
-                }
-            }
-
             for msg in client.recv::<NewPlayer>() {
                 spawn_player(msg.0, false, &mut commands, &mut *meshes, &mut *materials);
             }
@@ -492,40 +506,30 @@ mod game {
     }
 
     fn handle_cons(
-        my_cid: Option<Res<MyCId>>,
-        conf: Res<MyConfig>,
         mut players: ResMut<Players>,
-        server: Option<ResMut<Server>>,
+        mut client_entities: ResMut<ClientEntities>,
+        server: Option<Res<Server>>,
+        mut er_connected: EventReader<ClientConnected<Connection>>,
+        mut er_disconnected: EventReader<ClientDisconnected>,
         mut ew_sync_transform: EventWriter<SyncC<Transform>>,
         // For spawning player
         mut commands: Commands,
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<StandardMaterial>>,
     ) {
-        if let Some(mut server) = server {
-            let mut discon = vec![];
-            server.handle_disconnects(|cid, status| {
-                info!("Connection {cid} disconnected with status: \"{status}\"");
-                discon.push(cid);
-            });
-            for cid in discon {
-                server.broadcast(&DelPlayer(cid)).unwrap();
+        if let Some(server) = server {
+            // The player entities are despawned by `bevy-pigeon`'s `ClientEntities`.
+            for discon in er_disconnected.iter() {
+                let cid = discon.cid;
+                info!(
+                    "Connection {cid} disconnected with status: \"{}\"",
+                    discon.status
+                );
                 players.0.remove(&cid);
             }
 
-            let mut new_players = vec![];
-            server.handle_new_cons(|cid, con: Connection| {
-                if con.pass != conf.pass {
-                    (false, Response::Rejected(RejectReason::IncorrectPassword))
-                } else if players.0.len() > 2 {
-                    (false, Response::Rejected(RejectReason::MaxPlayersReached))
-                } else {
-                    new_players.push((cid, con.user));
-                    (true, Response::Accepted(cid))
-                }
-            });
-
-            for (cid, user) in new_players {
+            for connected in er_connected.iter() {
+                let cid = connected.cid;
                 // Tell the new client about all the existing clients.
                 for p_cid in players.0.keys() {
                     server.send_to(cid, &NewPlayer(*p_cid)).unwrap();
@@ -539,12 +543,10 @@ mod game {
                     .send_spec(CIdSpec::Except(cid), &NewPlayer(cid))
                     .unwrap();
 
-                players.0.insert(cid, user);
+                players.0.insert(cid, connected.request.user.clone());
 
-                // Stops the host from spawning two players for itself.
-                if my_cid.is_some() && my_cid.as_ref().unwrap().0 != cid {
-                    spawn_player(cid, false, &mut commands, &mut *meshes, &mut *materials);
-                }
+                let player = spawn_player(cid, false, &mut commands, &mut *meshes, &mut *materials);
+                client_entities.insert(cid, player);
             }
         }
     }
@@ -555,7 +557,7 @@ mod game {
         commands: &mut Commands,
         meshes: &mut Assets<Mesh>,
         materials: &mut Assets<StandardMaterial>,
-    ) {
+    ) -> Entity {
         info!("Spawning player. CId: {cid}, mine? {my_player}.");
 
         let net_comp = if my_player {
@@ -591,5 +593,6 @@ mod game {
         if my_player {
             commands.entity(id).insert(MyPlayer);
         }
+        id
     }
 }
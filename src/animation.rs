@@ -0,0 +1,259 @@
+//! Syncing animation playback.
+//!
+//! The playback state of an [`AnimationPlayer`] is sent as a
+//! [`NetAnimationState`](crate::types::NetAnimationState), using the same
+//! [`NetAssetComp`] as [`sync_comp_assets`](crate::AppExt::sync_comp_assets). When it is received,
+//! the playback is moved forward by the estimated one-way latency, so remote characters animate
+//! in step with the sender.
+//!
+//! The elapsed time changes every frame, so with change detection, a state is only sent when the
+//! clip, speed, or paused or repeat flags change, or when the playback is moved by more than
+//! [`SEEK_TOLERANCE`] from where it would have played to. Receivers play it forward themselves in
+//! between.
+
+use crate::app::{get_latest_msg, send_msg, send_target, Recorder};
+use crate::asset::{NetAssetComp, NetAssetConv, NetAssetKeys};
+use crate::clock::ServerTime;
+use crate::host::Host;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetCompMsg, NetEntity};
+use crate::types::NetAnimationState;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use carrier_pigeon::net::{CIdSpec, NetMsg};
+use carrier_pigeon::{Client, Server};
+
+/// How far, in seconds, the playback can be from where the last sent state would have played to,
+/// before it is sent again with change detection.
+pub const SEEK_TOLERANCE: f32 = 0.05;
+
+/// Whether `new` needs to be sent with change detection, if `last` was sent `secs` ago.
+fn needs_send(last: &NetAnimationState, secs: f32, new: &NetAnimationState) -> bool {
+    let mut predicted = last.clone();
+    predicted.advance(secs);
+    new.clip != last.clip
+        || new.speed != last.speed
+        || new.paused != last.paused
+        || new.repeat != last.repeat
+        || (new.elapsed - predicted.elapsed).abs() > SEEK_TOLERANCE
+}
+
+/// A system that sends the playback state of the [`AnimationPlayer`]s.
+///
+/// With change detection, the state is only sent when it differs from what the receivers predict;
+/// see the [module docs](self).
+///
+/// Most of the time, you will call [`sync_animation`](crate::AppExt::sync_animation) which will
+/// add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn animation_send(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    time: Res<Time>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    mut last_sent: Local<HashMap<Entity, (NetAnimationState, f32)>>,
+    q: Query<(
+        Entity,
+        &NetEntity,
+        &NetAssetComp<AnimationPlayer, NetAnimationState>,
+        &AnimationPlayer,
+        ChangeTrackers<AnimationPlayer>,
+    )>,
+) {
+    let now = time.elapsed_seconds();
+    last_sent.retain(|entity, _| q.contains(*entity));
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+
+    for (entity, net_e, net_c, player, ct) in q.iter() {
+        let to_spec = match send_target(
+            server.is_some(),
+            host.as_deref(),
+            net_c.cd,
+            ct.is_changed(),
+            net_c.c_dir,
+            net_c.s_dir,
+        ) {
+            Some(to_spec) => to_spec,
+            None => continue,
+        };
+
+        let state = match NetAnimationState::from_comp(player, assets.as_deref(), &keys) {
+            Some(state) => state,
+            None => {
+                warn!(
+                    "NetEntity {{ id: {} }} has an `AnimationPlayer` that can't be sent. Its clip needs an asset path or a key in `NetAssetKeys`.",
+                    net_e.id
+                );
+                continue;
+            }
+        };
+        if net_c.cd {
+            if let Some((last, sent_at)) = last_sent.get(&entity) {
+                if !needs_send(last, now - sent_at, &state) {
+                    continue;
+                }
+            }
+        }
+
+        let msg = NetCompMsg::new(net_e.id, state.clone());
+        send_msg(
+            server,
+            client,
+            spectators.as_deref(),
+            recorder.as_deref(),
+            to_spec,
+            &msg,
+        );
+        last_sent.insert(entity, (state, now));
+    }
+}
+
+/// A system that receives animation states and applies them to the [`AnimationPlayer`]s.
+///
+/// On the client, the playback is moved forward by half of the round trip time of the
+/// [`ServerTime`], if [`sync_clock`](crate::AppExt::sync_clock) is used. The server has no
+/// estimate, so it applies the state as is.
+///
+/// Most of the time, you will call [`sync_animation`](crate::AppExt::sync_animation) which will
+/// add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn animation_recv(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    spectators: Option<Res<Spectators>>,
+    server_time: Option<Res<ServerTime>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    mut q: Query<(
+        &NetEntity,
+        &mut NetAssetComp<AnimationPlayer, NetAnimationState>,
+        &mut AnimationPlayer,
+    )>,
+) {
+    let (msgs, latency): (Vec<NetMsg<NetCompMsg<NetAnimationState>>>, f32) =
+        if let Some(server) = &server {
+            // Ignore the messages from spectators.
+            let msgs = server
+                .recv::<NetCompMsg<NetAnimationState>>()
+                .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+                .collect();
+            (msgs, 0.0)
+        } else if let Some(client) = &client {
+            let latency = server_time
+                .filter(|server_time| server_time.is_synced())
+                .map_or(0.0, |server_time| server_time.rtt().as_secs_f32() / 2.0);
+            (
+                client.recv::<NetCompMsg<NetAnimationState>>().collect(),
+                latency,
+            )
+        } else {
+            return;
+        };
+
+    for (net_e, mut net_c, mut player) in q.iter_mut() {
+        let spec = if server.is_some() {
+            match net_c.s_dir.from() {
+                Some(spec) => *spec,
+                None => continue,
+            }
+        } else if net_c.c_dir == CNetDir::From {
+            CIdSpec::All
+        } else {
+            continue;
+        };
+
+        if let Some(valid_msg) = get_latest_msg(&msgs, net_c.last, spec, net_e.id) {
+            net_c.last = valid_msg.time;
+            let mut state = valid_msg.msg.clone();
+            state.advance(latency);
+            match state.clip.to_handle(assets.as_deref(), &keys) {
+                Some(clip) => state.apply(&mut player, clip),
+                None => warn!(
+                    "Received an animation state for NetEntity {{ id: {} }} that can't be resolved. Its asset key is not in `NetAssetKeys`, or there is no `AssetServer` to load its path.",
+                    net_e.id
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{NetAssetRef, NetHandle};
+    use bevy::asset::HandleId;
+    use bevy::reflect::Struct;
+
+    fn field<'a, T: Reflect>(player: &'a AnimationPlayer, name: &str) -> Option<&'a T> {
+        player.field(name)?.downcast_ref()
+    }
+
+    fn state(path: &str, elapsed: f32) -> NetAnimationState {
+        NetAnimationState {
+            clip: NetHandle::new(NetAssetRef::Path(path.into())),
+            elapsed,
+            speed: 2.0,
+            paused: false,
+            repeat: true,
+        }
+    }
+
+    #[test]
+    fn needs_send_playing() {
+        let last = state("walk.glb#Animation0", 1.0);
+        // Played forward as predicted.
+        assert!(!needs_send(&last, 0.5, &state("walk.glb#Animation0", 2.0)));
+        // Seeked.
+        assert!(needs_send(&last, 0.5, &state("walk.glb#Animation0", 0.0)));
+        // Changed clip.
+        assert!(needs_send(&last, 0.5, &state("run.glb#Animation0", 2.0)));
+
+        let mut paused = state("walk.glb#Animation0", 2.0);
+        paused.paused = true;
+        assert!(needs_send(&last, 0.5, &paused));
+    }
+
+    #[test]
+    fn needs_send_paused() {
+        let mut last = state("walk.glb#Animation0", 1.0);
+        last.paused = true;
+        let mut new = last.clone();
+        assert!(!needs_send(&last, 3.0, &new));
+        new.speed = 1.0;
+        assert!(needs_send(&last, 3.0, &new));
+    }
+
+    #[test]
+    fn apply_in_place() {
+        let clip = Handle::<AnimationClip>::weak(HandleId::random::<AnimationClip>());
+        let mut player = AnimationPlayer::default();
+        player.start(clip.clone()).set_elapsed(1.0);
+
+        let mut new = state("walk.glb#Animation0", 3.0);
+        new.paused = true;
+        new.apply(&mut player, clip.clone());
+        assert_eq!(player.elapsed(), 3.0);
+        assert_eq!(player.speed(), 2.0);
+        assert!(player.is_paused());
+        assert_eq!(field::<bool>(&player, "repeat"), Some(&true));
+
+        // A new clip restarts the player, then applies the state.
+        let other = Handle::<AnimationClip>::weak(HandleId::random::<AnimationClip>());
+        new.paused = false;
+        new.apply(&mut player, other.clone());
+        assert_eq!(
+            field::<Handle<AnimationClip>>(&player, "animation_clip"),
+            Some(&other)
+        );
+        assert_eq!(player.elapsed(), 3.0);
+        assert!(!player.is_paused());
+    }
+}
@@ -1,14 +1,69 @@
 //! Contains the plugins, systems, and components for the bevy app.
 
+#[cfg(feature = "animation")]
+use crate::animation::{animation_recv, animation_send};
+#[cfg(feature = "types")]
+use crate::asset::{asset_comp_recv, asset_comp_send, NetAssetConv, NetAssetKeys};
+#[cfg(feature = "audio")]
+use crate::audio::{audio_cue_send, play_audio_cues, NetAudioCue, NetAudioFalloff};
+use crate::bundle::{bundle_recv, bundle_send, send_bundle_on_event, NetBundleComps};
+use crate::client_entities::{despawn_client_entities, recv_despawns, ClientEntities, NetDespawn};
+use crate::clock::{client_clock_sync, server_clock_sync, ClockPing, ClockPong, ServerTime};
+use crate::config::NetConfig;
+use crate::connection::{
+    check_client_status, handle_disconnects, handle_new_cons, poll_pending_client, ClientAccept,
+    ClientConnected, ClientDisconnected, ConnectedToServer, ConnectionFailed, ConnectionRejected,
+    DisconnectedFromServer,
+};
+use crate::history::{record_history, HistoryMaxAge};
+use crate::host::{is_self_send, Host};
+#[cfg(feature = "types")]
+use crate::material::{material_recv, material_send};
+#[cfg(feature = "rapier")]
+use crate::physics::net_body_mode;
+use crate::protocol::{
+    client_check_protocol, server_check_protocol, NetProtocol, ProtocolCheck, ProtocolDiscon,
+    ProtocolMismatch, ProtocolTimeout, RegError,
+};
+#[cfg(feature = "reflect")]
+use crate::reflect::{reflect_recv, reflect_send, NetReflectMsg};
+use crate::relay::{relay_comp_msgs, remove_relay_owners, NetRelay};
+#[cfg(all(feature = "replay", feature = "animation"))]
+use crate::replay::replay_animation;
+#[cfg(all(feature = "replay", feature = "reflect"))]
+use crate::replay::replay_reflect;
+#[cfg(feature = "replay")]
+use crate::replay::{
+    advance_replay, record_recv, replay_bundle, replay_despawns, replay_recv, replay_res,
+    RecordDir, ReplayAdded,
+};
+#[cfg(all(feature = "replay", feature = "types"))]
+use crate::replay::{replay_asset_comp, replay_material, replay_world_transform};
+use crate::res::{res_recv, res_send, NetResMsg};
+use crate::spectator::{is_spectator, send_spec, Spectators};
 use crate::sync::{CNetDir, NetCompMsg, SNetDir};
 use crate::sync::{NetComp, NetEntity};
+#[cfg(feature = "animation")]
+use crate::types::NetAnimationState;
+#[cfg(feature = "types")]
+use crate::types::NetMaterial;
+#[cfg(feature = "rapier")]
+use crate::types::NetVelocity;
+#[cfg(feature = "types")]
+use crate::types::NetWorldTransform;
+#[cfg(feature = "types")]
+use crate::world::world_transform_recv;
 use bevy::prelude::*;
+#[cfg(feature = "rapier")]
+use bevy_rapier3d::prelude::Velocity;
 use carrier_pigeon::net::{CIdSpec, NetMsg};
-use carrier_pigeon::{Client, MsgRegError, MsgTable, Server, SortedMsgTable, Transport};
+use carrier_pigeon::{CId, Client, MsgRegError, MsgTable, Server, SortedMsgTable, Transport};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
+use std::time::Duration;
 
 /// An event that forces a sync of component `T`.
 ///
@@ -20,33 +75,170 @@ pub struct SyncC<T> {
     _pd: PhantomData<T>,
 }
 
-/// A label that is applied to all networking systems.
-#[derive(SystemLabel, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, Hash)]
-pub struct NetLabel;
+/// The labels that are applied to the networking systems.
+#[derive(SystemLabel, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub enum NetLabel {
+    /// Applied to the systems that receive messages.
+    Recv,
+    /// Applied to the systems that send messages.
+    Send,
+}
 
 /// The client plugin.
 ///
 /// Automatically clears client's message buffer and receive new messages at the start of every
 /// frame.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, Hash)]
-pub struct ClientPlugin;
+///
+/// It also polls the [`OptionPendingClient`](carrier_pigeon::OptionPendingClient) resource and
+/// fires the [`ConnectedToServer<R>`], [`ConnectionRejected<R>`], [`ConnectionFailed`] and
+/// [`DisconnectedFromServer`] events. `R` is the response message type.
+///
+/// Before 0.4, this was a unit struct. Use `ClientPlugin::<Response>::default()` in place of
+/// `ClientPlugin`.
+pub struct ClientPlugin<R> {
+    /// The config for the networking systems.
+    pub config: NetConfig,
+    /// Decides whether a response from the server is an acceptance. The `Client` resource is only
+    /// inserted if it is.
+    ///
+    /// Defaults to accepting every response.
+    pub accept: fn(&R) -> bool,
+}
 
 /// The server plugin.
 ///
 /// Automatically clears server's message buffer and receive new messages at the start of every
 /// frame.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, Hash)]
-pub struct ServerPlugin;
+///
+/// It also handles new connections and disconnects, firing the [`ClientConnected<C>`] and
+/// [`ClientDisconnected`] events. New connections are only handled if there is a
+/// [`ConnectionPolicy<C, R>`](crate::connection::ConnectionPolicy) resource. `C` is the
+/// connection message type and `R` is the response message type.
+///
+/// Before 0.4, this was a unit struct. Use `ServerPlugin::<Connection, Response>::default()` in
+/// place of `ServerPlugin`.
+pub struct ServerPlugin<C, R> {
+    /// The config for the networking systems.
+    pub config: NetConfig,
+    _pd: PhantomData<fn() -> (C, R)>,
+}
+
+impl<R> Default for ClientPlugin<R> {
+    fn default() -> Self {
+        Self::new(NetConfig::default())
+    }
+}
+
+impl<R> ClientPlugin<R> {
+    /// Creates a new [`ClientPlugin`] with the given config, that accepts every response.
+    pub fn new(config: NetConfig) -> Self {
+        ClientPlugin {
+            config,
+            accept: |_| true,
+        }
+    }
+
+    /// Uses `accept` to decide whether a response from the server is an acceptance.
+    ///
+    /// When it returns false, a [`ConnectionRejected<R>`] event is fired instead of inserting the
+    /// `Client` resource.
+    pub fn with_accept(mut self, accept: fn(&R) -> bool) -> Self {
+        self.accept = accept;
+        self
+    }
+}
+
+impl<R> Clone for ClientPlugin<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for ClientPlugin<R> {}
+
+impl<R> Debug for ClientPlugin<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(&format!("ClientPlugin<{}>", std::any::type_name::<R>()))
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<C, R> Default for ServerPlugin<C, R> {
+    fn default() -> Self {
+        Self::new(NetConfig::default())
+    }
+}
+
+impl<C, R> ServerPlugin<C, R> {
+    /// Creates a new [`ServerPlugin`] with the given config.
+    pub fn new(config: NetConfig) -> Self {
+        ServerPlugin {
+            config,
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<C, R> Clone for ServerPlugin<C, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, R> Copy for ServerPlugin<C, R> {}
+
+impl<C, R> Debug for ServerPlugin<C, R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(&format!(
+            "ServerPlugin<{}, {}>",
+            std::any::type_name::<C>(),
+            std::any::type_name::<R>()
+        ))
+        .field("config", &self.config)
+        .finish()
+    }
+}
 
-impl Plugin for ClientPlugin {
+impl<R: Any + Send + Sync> Plugin for ClientPlugin<R> {
     fn build(&self, app: &mut App) {
-        app.add_system_to_stage(CoreStage::First, client_tick.label(NetLabel));
+        let stage = self.config.recv_stage;
+        app.insert_resource(self.config)
+            .insert_resource(ClientAccept(self.accept))
+            .add_event::<ConnectedToServer<R>>()
+            .add_event::<ConnectionRejected<R>>()
+            .add_event::<ConnectionFailed>()
+            .add_event::<DisconnectedFromServer>()
+            .add_system_to_stage(stage, client_tick.label(NetLabel::Recv))
+            .add_system_to_stage(stage, poll_pending_client::<R>.label(NetLabel::Recv))
+            .add_system_to_stage(
+                stage,
+                check_client_status.label(NetLabel::Recv).after(client_tick),
+            );
     }
 }
 
-impl Plugin for ServerPlugin {
+impl<C, R> Plugin for ServerPlugin<C, R>
+where
+    C: Any + Send + Sync,
+    R: Any + Send + Sync + Clone,
+{
     fn build(&self, app: &mut App) {
-        app.add_system_to_stage(CoreStage::First, server_tick.label(NetLabel));
+        let stage = self.config.recv_stage;
+        app.insert_resource(self.config)
+            .add_event::<ClientConnected<C>>()
+            .add_event::<ClientDisconnected>()
+            .add_system_to_stage(stage, server_tick.label(NetLabel::Recv))
+            .add_system_to_stage(
+                stage,
+                handle_new_cons::<C, R>
+                    .label(NetLabel::Recv)
+                    .after(server_tick),
+            )
+            .add_system_to_stage(
+                stage,
+                handle_disconnects.label(NetLabel::Recv).after(server_tick),
+            );
     }
 }
 
@@ -118,15 +310,421 @@ pub trait AppExt {
 
     /// Adds everything needed to sync component `T` using message type `M`.
     ///
-    /// Same as [`sync_comp()`](App::sync_comp), but doesn't panic in the event of a [`MsgRegError`].
+    /// Same as [`sync_comp_sorted()`](AppExt::sync_comp_sorted), but doesn't panic in the event of
+    /// a [`RegError`].
     fn try_sync_comp_sorted<T, M>(
         &mut self,
         table: &mut SortedMsgTable,
         transport: Transport,
-    ) -> Result<&mut Self, MsgRegError>
+    ) -> Result<&mut Self, RegError>
+    where
+        T: Clone + Into<M> + Component,
+        M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync component `T` using message type `M`, with an explicit
+    /// message id.
+    ///
+    /// Same as [`sync_comp_sorted()`](AppExt::sync_comp_sorted), but uses `id` instead of the
+    /// type name of `M`. The type name changes when the type is moved or renamed, and is not
+    /// guaranteed to be the same across compiler versions, so prefer this when the peers may be
+    /// built separately.
+    ///
+    /// ### Panics
+    /// panics if `id` is already used by another message, or if `NetCompMsg<M>` is already
+    /// registered in the table.
+    fn sync_comp_sorted_with_id<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+        id: &str,
+    ) -> &mut Self
+    where
+        T: Clone + Into<M> + Component,
+        M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync component `T` using message type `M`, with an explicit
+    /// message id.
+    ///
+    /// Same as [`sync_comp_sorted_with_id()`](AppExt::sync_comp_sorted_with_id), but doesn't
+    /// panic in the event of a [`RegError`].
+    fn try_sync_comp_sorted_with_id<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+        id: &str,
+    ) -> Result<&mut Self, RegError>
     where
         T: Clone + Into<M> + Component,
         M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync component `T`, which holds asset handles, using message type
+    /// `M`.
+    ///
+    /// Same as [`sync_comp()`](AppExt::sync_comp), but `T` is converted to and from `M` with a
+    /// [`NetAssetConv`], which can turn handles into asset paths and back. The entities use a
+    /// [`NetAssetComp`](crate::asset::NetAssetComp) instead of a [`NetComp`]. Also inserts the
+    /// [`NetAssetKeys`] resource if there is none.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    #[cfg(feature = "types")]
+    fn sync_comp_assets<T, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        T: Component,
+        M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync component `T`, which holds asset handles, using message type
+    /// `M`.
+    ///
+    /// Same as [`sync_comp_assets()`](AppExt::sync_comp_assets), but for a [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_comp_assets_sorted<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        T: Component,
+        M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync the playback of [`AnimationPlayer`]s.
+    ///
+    /// Registers the type `NetCompMsg<NetAnimationState>` into `table` and adds the systems that
+    /// sync the clip, elapsed time, speed, paused and repeat flags of the entities with a
+    /// [`NetAssetComp<AnimationPlayer, NetAnimationState>`](crate::asset::NetAssetComp). The clip
+    /// is sent as its asset path. The receiver seeks forward to make up for the latency. With
+    /// change detection, the elapsed time is only sent when the playback is seeked.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetAnimationState>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "animation")]
+    fn sync_animation(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to sync the playback of [`AnimationPlayer`]s.
+    ///
+    /// Same as [`sync_animation()`](AppExt::sync_animation), but for a [`SortedMsgTable`].
+    #[cfg(feature = "animation")]
+    fn sync_animation_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to play [`NetAudioCue`]s on the clients.
+    ///
+    /// Registers the [`NetAudioCue`] message into `table`, adds it as an event, and adds the
+    /// systems that send the cues from the server and play them through bevy's [`Audio`]. Also
+    /// inserts the [`NetAudioFalloff`] and [`NetAssetKeys`] resources if there are none.
+    ///
+    /// ### Panics
+    /// panics if [`NetAudioCue`] is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "audio")]
+    fn sync_audio_cues(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to play [`NetAudioCue`]s on the clients.
+    ///
+    /// Same as [`sync_audio_cues()`](AppExt::sync_audio_cues), but for a [`SortedMsgTable`].
+    #[cfg(feature = "audio")]
+    fn sync_audio_cues_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to sync physics bodies from `bevy_rapier3d`.
+    ///
+    /// Syncs rapier's `Velocity` using [`NetVelocity`](crate::types::NetVelocity), like
+    /// [`sync_comp()`](AppExt::sync_comp), and adds the system that takes the bodies with a
+    /// [`NetBody`](crate::physics::NetBody) out of the local simulation on the peers that receive
+    /// them.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetVelocity>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "rapier")]
+    fn sync_physics(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to sync physics bodies from `bevy_rapier3d`.
+    ///
+    /// Same as [`sync_physics()`](AppExt::sync_physics), but for a [`SortedMsgTable`].
+    #[cfg(feature = "rapier")]
+    fn sync_physics_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to sync transforms in world space.
+    ///
+    /// Registers the type `NetCompMsg<NetWorldTransform>` into `table` and adds the systems that
+    /// send the [`GlobalTransform`] of the entities with a
+    /// `NetComp<GlobalTransform, NetWorldTransform>`. The receiver writes it to the [`Transform`],
+    /// relative to its own parent, so the entity ends up in the same place even if it has a
+    /// different parent there.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetWorldTransform>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "types")]
+    fn sync_world_transform(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to sync transforms in world space.
+    ///
+    /// Same as [`sync_world_transform()`](AppExt::sync_world_transform), but for a
+    /// [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_world_transform_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to sync the parameters of [`StandardMaterial`]s.
+    ///
+    /// Registers the type `NetCompMsg<NetMaterial>` into `table` and adds the systems that sync
+    /// the colors and parameters of the material of the entities with a
+    /// [`NetMaterialComp`](crate::material::NetMaterialComp). The receiver applies them to the
+    /// material its `Handle<StandardMaterial>` points to.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetMaterial>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "types")]
+    fn sync_materials(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to sync the parameters of [`StandardMaterial`]s.
+    ///
+    /// Same as [`sync_materials()`](AppExt::sync_materials), but for a [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_materials_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to sync resource `R` from the server to the clients, using message
+    /// type `M`.
+    ///
+    /// Registers the type `NetResMsg<M>` into `table` and adds the systems that send the resource
+    /// when it changes on the server, and to clients when they connect. The clients insert the
+    /// resource if they don't have it.
+    ///
+    /// ### Panics
+    /// panics if `NetResMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    fn sync_res<R, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        R: Resource + Clone + Into<M>,
+        M: Clone + Into<R> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync resource `R` from the server to the clients, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_res()`](AppExt::sync_res), but for a [`SortedMsgTable`].
+    fn sync_res_sorted<R, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        R: Resource + Clone + Into<M>,
+        M: Clone + Into<R> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Registers the type `NetCompMsg<M>` into `table` and adds the systems required to sync the
+    /// components of entities with a [`NetBundle<B, M>`](crate::bundle::NetBundle). All the
+    /// components are captured, sent and applied at once.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`, or use `M` for a component too).
+    fn sync_bundle<B, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle()`](AppExt::sync_bundle), but doesn't panic in the event of a
+    /// [`MsgRegError`].
+    fn try_sync_bundle<B, M>(
+        &mut self,
+        table: &mut MsgTable,
+        transport: Transport,
+    ) -> Result<&mut Self, MsgRegError>
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle()`](AppExt::sync_bundle), but for a [`SortedMsgTable`].
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`, or use `M` for a component too).
+    fn sync_bundle_sorted<B, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle_sorted()`](AppExt::sync_bundle_sorted), but doesn't panic in the
+    /// event of a [`RegError`].
+    fn try_sync_bundle_sorted<B, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> Result<&mut Self, RegError>
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to despawn the entities owned by a client when it disconnects.
+    ///
+    /// Registers the despawn message into `table`, inserts the [`ClientEntities`] resource, and
+    /// adds the systems that despawn the entities on the server and replicate the despawn to the
+    /// remaining clients.
+    ///
+    /// ### Panics
+    /// panics if the despawn message is already registered in the table
+    /// (If you call this method twice).
+    fn despawn_client_entities(&mut self, table: &mut MsgTable) -> &mut Self;
+
+    /// Adds everything needed to despawn the entities owned by a client when it disconnects.
+    ///
+    /// Same as [`despawn_client_entities()`](AppExt::despawn_client_entities), but for a
+    /// [`SortedMsgTable`].
+    fn despawn_client_entities_sorted(&mut self, table: &mut SortedMsgTable) -> &mut Self;
+
+    /// Adds everything needed to sync the components of entities with a
+    /// [`NetReflect`](crate::reflect::NetReflect), using bevy's reflection.
+    ///
+    /// Registers the reflected component message into `table` and adds the systems that send and
+    /// receive every registered component with the
+    /// [`ReflectReplicate`](crate::reflect::ReflectReplicate) type data.
+    ///
+    /// ### Panics
+    /// panics if the reflected component message is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "reflect")]
+    fn sync_reflect(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self;
+
+    /// Adds everything needed to sync the components of entities with a
+    /// [`NetReflect`](crate::reflect::NetReflect), using bevy's reflection.
+    ///
+    /// Same as [`sync_reflect()`](AppExt::sync_reflect), but for a [`SortedMsgTable`].
+    #[cfg(feature = "reflect")]
+    fn sync_reflect_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self;
+
+    /// Adds everything needed to relay the component messages of type `M` between clients.
+    ///
+    /// Registers the type `NetCompMsg<M>` into `table`, inserts the [`NetRelay`] resource if there
+    /// is none, and adds the systems that forward the messages from the owner of an entity to the
+    /// other clients. The server doesn't need the entity or the component.
+    ///
+    /// The clients register the same message with [`sync_comp`](AppExt::sync_comp). If the server
+    /// also syncs the component, add [`relay_comp_msgs`](crate::relay::relay_comp_msgs) manually
+    /// instead.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    fn relay_comp<M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        M: Clone + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to relay the component messages of type `M` between clients.
+    ///
+    /// Same as [`relay_comp()`](AppExt::relay_comp), but for a [`SortedMsgTable`].
+    fn relay_comp_sorted<M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        M: Clone + Any + Send + Sync + Serialize + DeserializeOwned;
+
+    /// Adds everything needed to check that clients use the same protocol as the server.
+    ///
+    /// Registers the protocol check message into `table`, and adds the systems that exchange the
+    /// protocol hash (see [`NetProtocol`]) when a client connects. Clients with a different
+    /// protocol are disconnected with `discon`, and a [`ProtocolMismatch`] event is fired on both
+    /// ends. Clients that don't send their hash within the [`ProtocolTimeout`] are disconnected
+    /// too.
+    ///
+    /// This must be called **before** any other messages are registered into the table. The id of
+    /// a message in a [`MsgTable`] depends on the order it was registered in, so this is the only
+    /// way for the protocol check message to have the same id on peers with different protocols.
+    ///
+    /// ### Panics
+    /// panics if the protocol check message is already registered in the table
+    /// (If you call this method twice), or if `bevy-pigeon` already registered other messages
+    /// into a [`MsgTable`].
+    fn check_protocol<D>(&mut self, table: &mut MsgTable, discon: D) -> &mut Self
+    where
+        D: Clone + Any + Send + Sync;
+
+    /// Adds everything needed to check that clients use the same protocol as the server.
+    ///
+    /// Same as [`check_protocol()`](AppExt::check_protocol), but for a [`SortedMsgTable`]. Since
+    /// the ids in a [`SortedMsgTable`] don't depend on the registration order, this can be called
+    /// at any point.
+    fn check_protocol_sorted<D>(&mut self, table: &mut SortedMsgTable, discon: D) -> &mut Self
+    where
+        D: Clone + Any + Send + Sync;
+
+    /// Adds everything needed to estimate the server's clock on the client.
+    ///
+    /// Registers the ping messages into `table`, inserts the [`ServerTime`] resource and adds the
+    /// systems that ping the server every `interval` and estimate the round trip time and clock
+    /// offset from the replies.
+    ///
+    /// ### Panics
+    /// panics if the ping messages are already registered in the table
+    /// (If you call this method twice).
+    fn sync_clock(&mut self, table: &mut MsgTable, interval: Duration) -> &mut Self;
+
+    /// Adds everything needed to estimate the server's clock on the client.
+    ///
+    /// Same as [`sync_clock()`](AppExt::sync_clock), but for a [`SortedMsgTable`].
+    fn sync_clock_sorted(&mut self, table: &mut SortedMsgTable, interval: Duration) -> &mut Self;
+
+    /// Keeps a history of component `T` of every [`NetEntity`] on the server, for lag
+    /// compensation.
+    ///
+    /// Values are kept for `max_age`. See [`CompHistory`](crate::history::CompHistory) and
+    /// [`rewind`](crate::history::rewind).
+    fn track_history<T>(&mut self, max_age: Duration) -> &mut Self
+    where
+        T: Component + Clone;
+
+    /// Records the received messages of type `T` when there is a
+    /// [`NetRecorder`](crate::replay::NetRecorder) resource.
+    ///
+    /// The messages registered by `bevy-pigeon` are already recorded. Use this for the messages
+    /// that you register yourself.
+    #[cfg(feature = "replay")]
+    fn record_msg<T>(&mut self) -> &mut Self
+    where
+        T: Any + Send + Sync + Serialize;
 }
 
 impl AppExt for App {
@@ -147,11 +745,9 @@ impl AppExt for App {
         M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
     {
         table.register::<NetCompMsg<M>>(transport).unwrap();
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
 
-        self.add_event::<SyncC<T>>();
-        self.add_system_to_stage(CoreStage::Last, send_on_event::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::Last, comp_send::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::First, comp_recv::<T, M>.label(NetLabel));
+        add_sync_systems::<T, M>(self);
         self
     }
 
@@ -168,11 +764,9 @@ impl AppExt for App {
         M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
     {
         table.register::<NetCompMsg<M>>(transport)?;
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
 
-        self.add_event::<SyncC<T>>();
-        self.add_system_to_stage(CoreStage::Last, send_on_event::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::Last, comp_send::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::First, comp_recv::<T, M>.label(NetLabel));
+        add_sync_systems::<T, M>(self);
         Ok(self)
     }
 
@@ -197,121 +791,1084 @@ impl AppExt for App {
         M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
     {
         let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
         table.register::<NetCompMsg<M>>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
 
-        self.add_event::<SyncC<T>>();
-        self.add_system_to_stage(CoreStage::Last, send_on_event::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::Last, comp_send::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::First, comp_recv::<T, M>.label(NetLabel));
+        add_sync_systems::<T, M>(self);
         self
     }
 
     /// Adds everything needed to sync component `T` using message type `M`.
     ///
-    /// Same as [`sync_comp()`](App::sync_comp), but doesn't panic in the event of a [`MsgRegError`].
+    /// Same as [`sync_comp_sorted()`](AppExt::sync_comp_sorted), but doesn't panic in the event of
+    /// a [`RegError`].
     fn try_sync_comp_sorted<T, M>(
         &mut self,
         table: &mut SortedMsgTable,
         transport: Transport,
-    ) -> Result<&mut Self, MsgRegError>
+    ) -> Result<&mut Self, RegError>
     where
         T: Clone + Into<M> + Component,
         M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
     {
         let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id)?;
         table.register::<NetCompMsg<M>>(transport, &id)?;
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
 
-        self.add_event::<SyncC<T>>();
-        self.add_system_to_stage(CoreStage::Last, send_on_event::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::Last, comp_send::<T, M>.label(NetLabel));
-        self.add_system_to_stage(CoreStage::First, comp_recv::<T, M>.label(NetLabel));
+        add_sync_systems::<T, M>(self);
         Ok(self)
     }
-}
-
-/// A system that forces a sync of a certain component.
-fn send_on_event<T, M>(
-    mut er: EventReader<SyncC<T>>,
-    server: Option<ResMut<Server>>,
-    client: Option<ResMut<Client>>,
-    q: Query<(&NetEntity, &NetComp<T, M>, &T)>,
-) where
-    T: Clone + Into<M> + Component,
-    M: Clone + Into<T> + Any + Send + Sync,
-{
-    if er.iter().count() == 0 {
-        return;
-    }
-    trace!("Force Syncing {}", std::any::type_name::<T>());
 
-    // Almost copy-paste from [`comp_send`] ignoring change detection
-    if let Some(server) = server {
-        for (net_e, net_c, comp) in q.iter() {
-            if let Some(to_spec) = net_c.s_dir.to() {
-                if let Err(e) = server.send_spec(
-                    *to_spec,
-                    &NetCompMsg::<M>::new(net_e.id, comp.clone().into()),
-                ) {
-                    error!("{}", e);
-                }
-            }
-        }
-    } else if let Some(client) = client {
-        for (net_e, net_c, comp) in q.iter() {
-            if let CNetDir::To = net_c.c_dir {
-                if let Err(e) = client.send(&NetCompMsg::<M>::new(net_e.id, comp.clone().into())) {
-                    error!("{}", e);
-                }
-            }
-        }
+    /// Adds everything needed to sync component `T` using message type `M`, with an explicit
+    /// message id.
+    ///
+    /// Same as [`sync_comp_sorted()`](AppExt::sync_comp_sorted), but uses `id` instead of the
+    /// type name of `M`. The type name changes when the type is moved or renamed, and is not
+    /// guaranteed to be the same across compiler versions, so prefer this when the peers may be
+    /// built separately.
+    ///
+    /// ### Panics
+    /// panics if `id` is already used by another message, or if `NetCompMsg<M>` is already
+    /// registered in the table.
+    fn sync_comp_sorted_with_id<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+        id: &str,
+    ) -> &mut Self
+    where
+        T: Clone + Into<M> + Component,
+        M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        self.try_sync_comp_sorted_with_id::<T, M>(table, transport, id)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
-}
 
-/// A system that sends component `T` using messages of type `M`.
-///
-/// Most of the time, you will call [`sync_comp`](AppExt::sync_comp) which will add this system.
-/// Only add it manually if you know what you are doing and want custom control over when it runs.
+    /// Adds everything needed to sync component `T` using message type `M`, with an explicit
+    /// message id.
+    ///
+    /// Same as [`sync_comp_sorted_with_id()`](AppExt::sync_comp_sorted_with_id), but doesn't
+    /// panic in the event of a [`RegError`].
+    fn try_sync_comp_sorted_with_id<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+        id: &str,
+    ) -> Result<&mut Self, RegError>
+    where
+        T: Clone + Into<M> + Component,
+        M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        check_msg_id::<NetCompMsg<M>>(self, id)?;
+        table.register::<NetCompMsg<M>>(transport, id)?;
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, id, transport);
+
+        add_sync_systems::<T, M>(self);
+        Ok(self)
+    }
+
+    /// Adds everything needed to sync component `T`, which holds asset handles, using message type
+    /// `M`.
+    ///
+    /// Same as [`sync_comp()`](AppExt::sync_comp), but `T` is converted to and from `M` with a
+    /// [`NetAssetConv`], which can turn handles into asset paths and back. The entities use a
+    /// [`NetAssetComp`](crate::asset::NetAssetComp) instead of a [`NetComp`]. Also inserts the
+    /// [`NetAssetKeys`] resource if there is none.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    #[cfg(feature = "types")]
+    fn sync_comp_assets<T, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        T: Component,
+        M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        table.register::<NetCompMsg<M>>(transport).unwrap();
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
+
+        add_asset_systems::<T, M>(self)
+    }
+
+    /// Adds everything needed to sync component `T`, which holds asset handles, using message type
+    /// `M`.
+    ///
+    /// Same as [`sync_comp_assets()`](AppExt::sync_comp_assets), but for a [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_comp_assets_sorted<T, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        T: Component,
+        M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetCompMsg<M>>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
+
+        add_asset_systems::<T, M>(self)
+    }
+
+    /// Adds everything needed to sync the playback of [`AnimationPlayer`]s.
+    ///
+    /// Registers the type `NetCompMsg<NetAnimationState>` into `table` and adds the systems that
+    /// sync the clip, elapsed time, speed, paused and repeat flags of the entities with a
+    /// [`NetAssetComp<AnimationPlayer, NetAnimationState>`](crate::asset::NetAssetComp). The clip
+    /// is sent as its asset path. The receiver seeks forward to make up for the latency. With
+    /// change detection, the elapsed time is only sent when the playback is seeked.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetAnimationState>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "animation")]
+    fn sync_animation(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        table
+            .register::<NetCompMsg<NetAnimationState>>(transport)
+            .unwrap();
+        record_protocol_msg::<NetCompMsg<NetAnimationState>>(self, transport);
+
+        add_animation_systems(self)
+    }
+
+    /// Adds everything needed to sync the playback of [`AnimationPlayer`]s.
+    ///
+    /// Same as [`sync_animation()`](AppExt::sync_animation), but for a [`SortedMsgTable`].
+    #[cfg(feature = "animation")]
+    fn sync_animation_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetAnimationState>();
+        check_msg_id::<NetCompMsg<NetAnimationState>>(self, &id)
+            .unwrap_or_else(|e| panic!("{}", e));
+        table
+            .register::<NetCompMsg<NetAnimationState>>(transport, &id)
+            .unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<NetAnimationState>>(self, &id, transport);
+
+        add_animation_systems(self)
+    }
+
+    /// Adds everything needed to play [`NetAudioCue`]s on the clients.
+    ///
+    /// Registers the [`NetAudioCue`] message into `table`, adds it as an event, and adds the
+    /// systems that send the cues from the server and play them through bevy's [`Audio`]. Also
+    /// inserts the [`NetAudioFalloff`] and [`NetAssetKeys`] resources if there are none.
+    ///
+    /// ### Panics
+    /// panics if [`NetAudioCue`] is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "audio")]
+    fn sync_audio_cues(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        table.register::<NetAudioCue>(transport).unwrap();
+        record_protocol_msg::<NetAudioCue>(self, transport);
+
+        add_audio_systems(self)
+    }
+
+    /// Adds everything needed to play [`NetAudioCue`]s on the clients.
+    ///
+    /// Same as [`sync_audio_cues()`](AppExt::sync_audio_cues), but for a [`SortedMsgTable`].
+    #[cfg(feature = "audio")]
+    fn sync_audio_cues_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetAudioCue>();
+        check_msg_id::<NetAudioCue>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetAudioCue>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetAudioCue>(self, &id, transport);
+
+        add_audio_systems(self)
+    }
+
+    /// Adds everything needed to sync physics bodies from `bevy_rapier3d`.
+    ///
+    /// Syncs rapier's `Velocity` using [`NetVelocity`](crate::types::NetVelocity), like
+    /// [`sync_comp()`](AppExt::sync_comp), and adds the system that takes the bodies with a
+    /// [`NetBody`](crate::physics::NetBody) out of the local simulation on the peers that receive
+    /// them.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetVelocity>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "rapier")]
+    fn sync_physics(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        self.sync_comp::<Velocity, NetVelocity>(table, transport);
+        add_physics_systems(self)
+    }
+
+    /// Adds everything needed to sync physics bodies from `bevy_rapier3d`.
+    ///
+    /// Same as [`sync_physics()`](AppExt::sync_physics), but for a [`SortedMsgTable`].
+    #[cfg(feature = "rapier")]
+    fn sync_physics_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        self.sync_comp_sorted::<Velocity, NetVelocity>(table, transport);
+        add_physics_systems(self)
+    }
+
+    /// Adds everything needed to sync transforms in world space.
+    ///
+    /// Registers the type `NetCompMsg<NetWorldTransform>` into `table` and adds the systems that
+    /// send the [`GlobalTransform`] of the entities with a
+    /// `NetComp<GlobalTransform, NetWorldTransform>`. The receiver writes it to the [`Transform`],
+    /// relative to its own parent, so the entity ends up in the same place even if it has a
+    /// different parent there.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetWorldTransform>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "types")]
+    fn sync_world_transform(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        table
+            .register::<NetCompMsg<NetWorldTransform>>(transport)
+            .unwrap();
+        record_protocol_msg::<NetCompMsg<NetWorldTransform>>(self, transport);
+
+        add_world_transform_systems(self)
+    }
+
+    /// Adds everything needed to sync transforms in world space.
+    ///
+    /// Same as [`sync_world_transform()`](AppExt::sync_world_transform), but for a
+    /// [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_world_transform_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetWorldTransform>();
+        check_msg_id::<NetCompMsg<NetWorldTransform>>(self, &id)
+            .unwrap_or_else(|e| panic!("{}", e));
+        table
+            .register::<NetCompMsg<NetWorldTransform>>(transport, &id)
+            .unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<NetWorldTransform>>(self, &id, transport);
+
+        add_world_transform_systems(self)
+    }
+
+    /// Adds everything needed to sync the parameters of [`StandardMaterial`]s.
+    ///
+    /// Registers the type `NetCompMsg<NetMaterial>` into `table` and adds the systems that sync
+    /// the colors and parameters of the material of the entities with a
+    /// [`NetMaterialComp`](crate::material::NetMaterialComp). The receiver applies them to the
+    /// material its `Handle<StandardMaterial>` points to.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<NetMaterial>` is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "types")]
+    fn sync_materials(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        table
+            .register::<NetCompMsg<NetMaterial>>(transport)
+            .unwrap();
+        record_protocol_msg::<NetCompMsg<NetMaterial>>(self, transport);
+
+        add_material_systems(self)
+    }
+
+    /// Adds everything needed to sync the parameters of [`StandardMaterial`]s.
+    ///
+    /// Same as [`sync_materials()`](AppExt::sync_materials), but for a [`SortedMsgTable`].
+    #[cfg(feature = "types")]
+    fn sync_materials_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetMaterial>();
+        check_msg_id::<NetCompMsg<NetMaterial>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table
+            .register::<NetCompMsg<NetMaterial>>(transport, &id)
+            .unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<NetMaterial>>(self, &id, transport);
+
+        add_material_systems(self)
+    }
+
+    /// Adds everything needed to sync resource `R` from the server to the clients, using message
+    /// type `M`.
+    ///
+    /// Registers the type `NetResMsg<M>` into `table` and adds the systems that send the resource
+    /// when it changes on the server, and to clients when they connect. The clients insert the
+    /// resource if they don't have it.
+    ///
+    /// ### Panics
+    /// panics if `NetResMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    fn sync_res<R, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        R: Resource + Clone + Into<M>,
+        M: Clone + Into<R> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        table.register::<NetResMsg<M>>(transport).unwrap();
+        record_protocol_msg::<NetResMsg<M>>(self, transport);
+
+        add_res_systems::<R, M>(self)
+    }
+
+    /// Adds everything needed to sync resource `R` from the server to the clients, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_res()`](AppExt::sync_res), but for a [`SortedMsgTable`].
+    fn sync_res_sorted<R, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        R: Resource + Clone + Into<M>,
+        M: Clone + Into<R> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        // Prefixed, so that it doesn't collide with the id of `sync_comp_sorted` with the same `M`.
+        let id = "bevy-pigeon::res::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetResMsg<M>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetResMsg<M>>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetResMsg<M>>(self, &id, transport);
+
+        add_res_systems::<R, M>(self)
+    }
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Registers the type `NetCompMsg<M>` into `table` and adds the systems required to sync the
+    /// components of entities with a [`NetBundle<B, M>`](crate::bundle::NetBundle). All the
+    /// components are captured, sent and applied at once.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`, or use `M` for a component too).
+    fn sync_bundle<B, M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        table.register::<NetCompMsg<M>>(transport).unwrap();
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
+
+        add_bundle_systems::<B, M>(self);
+        self
+    }
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle()`](AppExt::sync_bundle), but doesn't panic in the event of a
+    /// [`MsgRegError`].
+    fn try_sync_bundle<B, M>(
+        &mut self,
+        table: &mut MsgTable,
+        transport: Transport,
+    ) -> Result<&mut Self, MsgRegError>
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        table.register::<NetCompMsg<M>>(transport)?;
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
+
+        add_bundle_systems::<B, M>(self);
+        Ok(self)
+    }
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle()`](AppExt::sync_bundle), but for a [`SortedMsgTable`].
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`, or use `M` for a component too).
+    fn sync_bundle_sorted<B, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetCompMsg<M>>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
+
+        add_bundle_systems::<B, M>(self);
+        self
+    }
+
+    /// Adds everything needed to sync the components in the tuple `B` together, using message
+    /// type `M`.
+    ///
+    /// Same as [`sync_bundle_sorted()`](AppExt::sync_bundle_sorted), but doesn't panic in the
+    /// event of a [`RegError`].
+    fn try_sync_bundle_sorted<B, M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> Result<&mut Self, RegError>
+    where
+        B: NetBundleComps + Into<M>,
+        M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id)?;
+        table.register::<NetCompMsg<M>>(transport, &id)?;
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
+
+        add_bundle_systems::<B, M>(self);
+        Ok(self)
+    }
+
+    /// Adds everything needed to despawn the entities owned by a client when it disconnects.
+    ///
+    /// Registers the despawn message into `table`, inserts the [`ClientEntities`] resource, and
+    /// adds the systems that despawn the entities on the server and replicate the despawn to the
+    /// remaining clients.
+    ///
+    /// ### Panics
+    /// panics if the despawn message is already registered in the table
+    /// (If you call this method twice).
+    fn despawn_client_entities(&mut self, table: &mut MsgTable) -> &mut Self {
+        table.register::<NetDespawn>(Transport::TCP).unwrap();
+        record_protocol_msg::<NetDespawn>(self, Transport::TCP);
+
+        add_client_entities_systems(self)
+    }
+
+    /// Adds everything needed to despawn the entities owned by a client when it disconnects.
+    ///
+    /// Same as [`despawn_client_entities()`](AppExt::despawn_client_entities), but for a
+    /// [`SortedMsgTable`].
+    fn despawn_client_entities_sorted(&mut self, table: &mut SortedMsgTable) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetDespawn>();
+        check_msg_id::<NetDespawn>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetDespawn>(Transport::TCP, &id).unwrap();
+        record_protocol_msg_sorted::<NetDespawn>(self, &id, Transport::TCP);
+
+        add_client_entities_systems(self)
+    }
+
+    /// Adds everything needed to sync the components of entities with a
+    /// [`NetReflect`](crate::reflect::NetReflect), using bevy's reflection.
+    ///
+    /// Registers the reflected component message into `table` and adds the systems that send and
+    /// receive every registered component with the
+    /// [`ReflectReplicate`](crate::reflect::ReflectReplicate) type data.
+    ///
+    /// ### Panics
+    /// panics if the reflected component message is already registered in the table
+    /// (If you call this method twice).
+    #[cfg(feature = "reflect")]
+    fn sync_reflect(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self {
+        table.register::<NetReflectMsg>(transport).unwrap();
+        record_protocol_msg::<NetReflectMsg>(self, transport);
+
+        add_reflect_systems(self)
+    }
+
+    /// Adds everything needed to sync the components of entities with a
+    /// [`NetReflect`](crate::reflect::NetReflect), using bevy's reflection.
+    ///
+    /// Same as [`sync_reflect()`](AppExt::sync_reflect), but for a [`SortedMsgTable`].
+    #[cfg(feature = "reflect")]
+    fn sync_reflect_sorted(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<NetReflectMsg>();
+        check_msg_id::<NetReflectMsg>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetReflectMsg>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetReflectMsg>(self, &id, transport);
+
+        add_reflect_systems(self)
+    }
+
+    /// Adds everything needed to relay the component messages of type `M` between clients.
+    ///
+    /// Registers the type `NetCompMsg<M>` into `table`, inserts the [`NetRelay`] resource if there
+    /// is none, and adds the systems that forward the messages from the owner of an entity to the
+    /// other clients. The server doesn't need the entity or the component.
+    ///
+    /// The clients register the same message with [`sync_comp`](AppExt::sync_comp). If the server
+    /// also syncs the component, add [`relay_comp_msgs`](crate::relay::relay_comp_msgs) manually
+    /// instead.
+    ///
+    /// ### Panics
+    /// panics if `NetCompMsg<M>` is already registered in the table
+    /// (If you call this method twice with the same `M`).
+    fn relay_comp<M>(&mut self, table: &mut MsgTable, transport: Transport) -> &mut Self
+    where
+        M: Clone + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        table.register::<NetCompMsg<M>>(transport).unwrap();
+        record_protocol_msg::<NetCompMsg<M>>(self, transport);
+
+        add_relay_systems::<M>(self)
+    }
+
+    /// Adds everything needed to relay the component messages of type `M` between clients.
+    ///
+    /// Same as [`relay_comp()`](AppExt::relay_comp), but for a [`SortedMsgTable`].
+    fn relay_comp_sorted<M>(
+        &mut self,
+        table: &mut SortedMsgTable,
+        transport: Transport,
+    ) -> &mut Self
+    where
+        M: Clone + Any + Send + Sync + Serialize + DeserializeOwned,
+    {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<M>();
+        check_msg_id::<NetCompMsg<M>>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table.register::<NetCompMsg<M>>(transport, &id).unwrap();
+        record_protocol_msg_sorted::<NetCompMsg<M>>(self, &id, transport);
+
+        add_relay_systems::<M>(self)
+    }
+
+    /// Adds everything needed to check that clients use the same protocol as the server.
+    ///
+    /// Registers the protocol check message into `table`, and adds the systems that exchange the
+    /// protocol hash (see [`NetProtocol`]) when a client connects. Clients with a different
+    /// protocol are disconnected with `discon`, and a [`ProtocolMismatch`] event is fired on both
+    /// ends. Clients that don't send their hash within the [`ProtocolTimeout`] are disconnected
+    /// too.
+    ///
+    /// This must be called **before** any other messages are registered into the table. The id of
+    /// a message in a [`MsgTable`] depends on the order it was registered in, so this is the only
+    /// way for the protocol check message to have the same id on peers with different protocols.
+    ///
+    /// ### Panics
+    /// panics if the protocol check message is already registered in the table
+    /// (If you call this method twice), or if `bevy-pigeon` already registered other messages
+    /// into a [`MsgTable`].
+    fn check_protocol<D>(&mut self, table: &mut MsgTable, discon: D) -> &mut Self
+    where
+        D: Clone + Any + Send + Sync,
+    {
+        let registered = self
+            .world
+            .get_resource::<NetProtocol>()
+            .map_or(false, NetProtocol::has_ordered);
+        assert!(
+            !registered,
+            "`check_protocol` must be called before any other messages are registered into the `MsgTable`"
+        );
+        table.register::<ProtocolCheck>(Transport::TCP).unwrap();
+        record_protocol_msg::<ProtocolCheck>(self, Transport::TCP);
+
+        add_protocol_systems(self, discon)
+    }
+
+    /// Adds everything needed to check that clients use the same protocol as the server.
+    ///
+    /// Same as [`check_protocol()`](AppExt::check_protocol), but for a [`SortedMsgTable`]. Since
+    /// the ids in a [`SortedMsgTable`] don't depend on the registration order, this can be called
+    /// at any point.
+    fn check_protocol_sorted<D>(&mut self, table: &mut SortedMsgTable, discon: D) -> &mut Self
+    where
+        D: Clone + Any + Send + Sync,
+    {
+        let id = "bevy-pigeon::".to_owned() + std::any::type_name::<ProtocolCheck>();
+        check_msg_id::<ProtocolCheck>(self, &id).unwrap_or_else(|e| panic!("{}", e));
+        table
+            .register::<ProtocolCheck>(Transport::TCP, &id)
+            .unwrap();
+        record_protocol_msg_sorted::<ProtocolCheck>(self, &id, Transport::TCP);
+
+        add_protocol_systems(self, discon)
+    }
+
+    /// Adds everything needed to estimate the server's clock on the client.
+    ///
+    /// Registers the ping messages into `table`, inserts the [`ServerTime`] resource and adds the
+    /// systems that ping the server every `interval` and estimate the round trip time and clock
+    /// offset from the replies.
+    ///
+    /// ### Panics
+    /// panics if the ping messages are already registered in the table
+    /// (If you call this method twice).
+    fn sync_clock(&mut self, table: &mut MsgTable, interval: Duration) -> &mut Self {
+        table.register::<ClockPing>(Transport::UDP).unwrap();
+        table.register::<ClockPong>(Transport::UDP).unwrap();
+        record_protocol_msg::<ClockPing>(self, Transport::UDP);
+        record_protocol_msg::<ClockPong>(self, Transport::UDP);
+
+        add_clock_systems(self, interval)
+    }
+
+    /// Adds everything needed to estimate the server's clock on the client.
+    ///
+    /// Same as [`sync_clock()`](AppExt::sync_clock), but for a [`SortedMsgTable`].
+    fn sync_clock_sorted(&mut self, table: &mut SortedMsgTable, interval: Duration) -> &mut Self {
+        let ping_id = "bevy-pigeon::".to_owned() + std::any::type_name::<ClockPing>();
+        let pong_id = "bevy-pigeon::".to_owned() + std::any::type_name::<ClockPong>();
+        check_msg_id::<ClockPing>(self, &ping_id).unwrap_or_else(|e| panic!("{}", e));
+        check_msg_id::<ClockPong>(self, &pong_id).unwrap_or_else(|e| panic!("{}", e));
+        table
+            .register::<ClockPing>(Transport::UDP, &ping_id)
+            .unwrap();
+        table
+            .register::<ClockPong>(Transport::UDP, &pong_id)
+            .unwrap();
+        record_protocol_msg_sorted::<ClockPing>(self, &ping_id, Transport::UDP);
+        record_protocol_msg_sorted::<ClockPong>(self, &pong_id, Transport::UDP);
+
+        add_clock_systems(self, interval)
+    }
+
+    /// Keeps a history of component `T` of every [`NetEntity`] on the server, for lag
+    /// compensation.
+    ///
+    /// Values are kept for `max_age`. See [`CompHistory`](crate::history::CompHistory) and
+    /// [`rewind`](crate::history::rewind).
+    fn track_history<T>(&mut self, max_age: Duration) -> &mut Self
+    where
+        T: Component + Clone,
+    {
+        let config = NetConfig::get(self);
+        self.insert_resource(HistoryMaxAge::<T>::new(max_age))
+            .add_system_to_stage(
+                config.send_stage,
+                record_history::<T>.before(NetLabel::Send),
+            )
+    }
+
+    /// Records the received messages of type `T` when there is a
+    /// [`NetRecorder`](crate::replay::NetRecorder) resource.
+    ///
+    /// The messages registered by `bevy-pigeon` are already recorded. Use this for the messages
+    /// that you register yourself.
+    #[cfg(feature = "replay")]
+    fn record_msg<T>(&mut self) -> &mut Self
+    where
+        T: Any + Send + Sync + Serialize,
+    {
+        add_record_systems::<T>(self);
+        self
+    }
+}
+
+/// Adds the systems for [`sync_comp`](AppExt::sync_comp).
+fn add_sync_systems<T, M>(app: &mut App)
+where
+    T: Clone + Into<M> + Component,
+    M: Clone + Into<T> + Any + Send + Sync + Serialize + DeserializeOwned,
+{
+    let config = NetConfig::get(app);
+    app.add_event::<SyncC<T>>();
+    config.add_send_system(app, send_on_event::<T, M>);
+    config.add_send_system(app, comp_send::<T, M>);
+    config.add_recv_system(app, comp_recv::<T, M>);
+
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_recv::<T, M>.label(NetLabel::Recv));
+}
+
+/// Adds the systems for [`sync_comp_assets`](AppExt::sync_comp_assets).
+#[cfg(feature = "types")]
+fn add_asset_systems<T, M>(app: &mut App) -> &mut App
+where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize + DeserializeOwned,
+{
+    let config = NetConfig::get(app);
+    app.init_resource::<NetAssetKeys>();
+    config.add_send_system(app, asset_comp_send::<T, M>);
+    config.add_recv_system(app, asset_comp_recv::<T, M>);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(
+        config.recv_stage,
+        replay_asset_comp::<T, M>.label(NetLabel::Recv),
+    );
+    app
+}
+
+/// Adds the systems for [`sync_animation`](AppExt::sync_animation).
+#[cfg(feature = "animation")]
+fn add_animation_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    app.init_resource::<NetAssetKeys>();
+    config.add_send_system(app, animation_send);
+    config.add_recv_system(app, animation_recv);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_animation.label(NetLabel::Recv));
+    app
+}
+
+/// Adds the systems for [`sync_audio_cues`](AppExt::sync_audio_cues).
+#[cfg(feature = "audio")]
+fn add_audio_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    app.add_event::<NetAudioCue>()
+        .init_resource::<NetAudioFalloff>()
+        .init_resource::<NetAssetKeys>();
+    config.add_send_system(app, audio_cue_send);
+    // Local cues are played while disconnected too, so this doesn't use the run criteria.
+    app.add_system_to_stage(config.recv_stage, play_audio_cues.label(NetLabel::Recv))
+}
+
+/// Adds the systems for [`sync_physics`](AppExt::sync_physics).
+#[cfg(feature = "rapier")]
+fn add_physics_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    app.add_system_to_stage(config.recv_stage, net_body_mode.after(NetLabel::Recv))
+}
+
+/// Adds the systems for [`sync_world_transform`](AppExt::sync_world_transform).
+#[cfg(feature = "types")]
+fn add_world_transform_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    config.add_send_system(app, comp_send::<GlobalTransform, NetWorldTransform>);
+    config.add_recv_system(app, world_transform_recv);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(
+        config.recv_stage,
+        replay_world_transform.label(NetLabel::Recv),
+    );
+    app
+}
+
+/// Adds the systems for [`sync_materials`](AppExt::sync_materials).
+#[cfg(feature = "types")]
+fn add_material_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    // The modified events are skipped while disconnected, so this doesn't use the run criteria.
+    app.add_system_to_stage(config.send_stage, material_send.label(NetLabel::Send));
+    config.add_recv_system(app, material_recv);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_material.label(NetLabel::Recv));
+    app
+}
+
+/// Adds the systems for [`sync_res`](AppExt::sync_res).
+fn add_res_systems<R, M>(app: &mut App) -> &mut App
+where
+    R: Resource + Clone + Into<M>,
+    M: Clone + Into<R> + Any + Send + Sync + Serialize + DeserializeOwned,
+{
+    let config = NetConfig::get(app);
+    config.add_send_system(app, res_send::<R, M>);
+    config.add_recv_system(app, res_recv::<R, M>);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_res::<R, M>.label(NetLabel::Recv));
+    app
+}
+
+/// Adds the systems for [`sync_bundle`](AppExt::sync_bundle).
+fn add_bundle_systems<B, M>(app: &mut App)
+where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync + Serialize + DeserializeOwned,
+{
+    let config = NetConfig::get(app);
+    app.add_event::<SyncC<B>>();
+    config.add_send_system(app, send_bundle_on_event::<B, M>);
+    config.add_send_system(app, bundle_send::<B, M>);
+    config.add_recv_system(app, bundle_recv::<B, M>);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(
+        config.recv_stage,
+        replay_bundle::<B, M>.label(NetLabel::Recv),
+    );
+}
+
+/// Adds the systems for [`despawn_client_entities`](AppExt::despawn_client_entities).
+fn add_client_entities_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    app.init_resource::<ClientEntities>();
+    app.add_event::<ClientDisconnected>();
+    config.add_recv_system(app, despawn_client_entities);
+    config.add_recv_system(app, recv_despawns);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_despawns.label(NetLabel::Recv));
+    app
+}
+
+/// Adds the systems for [`relay_comp`](AppExt::relay_comp).
+fn add_relay_systems<M>(app: &mut App) -> &mut App
+where
+    M: Clone + Any + Send + Sync + Serialize,
+{
+    let config = NetConfig::get(app);
+    app.init_resource::<NetRelay>();
+    app.add_event::<ClientDisconnected>();
+    config.add_recv_system(app, relay_comp_msgs::<M>);
+    config.add_recv_system(app, remove_relay_owners);
+    app
+}
+
+/// Adds the systems for [`sync_reflect`](AppExt::sync_reflect).
+#[cfg(feature = "reflect")]
+fn add_reflect_systems(app: &mut App) -> &mut App {
+    let config = NetConfig::get(app);
+    config.add_send_system(app, reflect_send);
+    config.add_recv_system(app, reflect_recv);
+    // The replay runs while disconnected, so it doesn't use the run criteria.
+    #[cfg(feature = "replay")]
+    app.add_system_to_stage(config.recv_stage, replay_reflect.label(NetLabel::Recv));
+    app
+}
+
+/// Adds the systems for [`check_protocol`](AppExt::check_protocol).
+fn add_protocol_systems<D>(app: &mut App, discon: D) -> &mut App
+where
+    D: Clone + Any + Send + Sync,
+{
+    let stage = NetConfig::get(app).recv_stage;
+    app.insert_resource(ProtocolDiscon(discon))
+        .init_resource::<ProtocolTimeout>()
+        .add_event::<ProtocolMismatch>()
+        .add_system_to_stage(stage, client_check_protocol.label(NetLabel::Recv))
+        .add_system_to_stage(stage, server_check_protocol::<D>.label(NetLabel::Recv))
+}
+
+/// Adds the systems for [`sync_clock`](AppExt::sync_clock).
+fn add_clock_systems(app: &mut App, interval: Duration) -> &mut App {
+    let stage = NetConfig::get(app).recv_stage;
+    app.insert_resource(ServerTime::new(interval))
+        .add_system_to_stage(stage, client_clock_sync.label(NetLabel::Recv))
+        .add_system_to_stage(stage, server_clock_sync.label(NetLabel::Recv))
+}
+
+/// Records a message registered into a [`MsgTable`] in the [`NetProtocol`].
+fn record_protocol_msg<T>(app: &mut App, transport: Transport)
+where
+    T: Any + Send + Sync + Serialize,
+{
+    app.world
+        .get_resource_or_insert_with(NetProtocol::default)
+        .record::<T>(transport);
+    #[cfg(feature = "replay")]
+    add_record_systems::<T>(app);
+}
+
+/// Records a message registered into a [`SortedMsgTable`] in the [`NetProtocol`].
+fn record_protocol_msg_sorted<T>(app: &mut App, id: &str, transport: Transport)
+where
+    T: Any + Send + Sync + Serialize,
+{
+    app.world
+        .get_resource_or_insert_with(NetProtocol::default)
+        .record_sorted::<T>(id, transport);
+    #[cfg(feature = "replay")]
+    add_record_systems::<T>(app);
+}
+
+/// Adds the systems that record the received messages of type `T`, and the one that moves the
+/// replay forward if it isn't added yet.
+#[cfg(feature = "replay")]
+fn add_record_systems<T>(app: &mut App)
+where
+    T: Any + Send + Sync + Serialize,
+{
+    let config = NetConfig::get(app);
+    config.add_recv_system(app, record_recv::<T>);
+    if !app.world.contains_resource::<ReplayAdded>() {
+        app.insert_resource(ReplayAdded)
+            .add_system_to_stage(config.recv_stage, advance_replay.before(NetLabel::Recv));
+    }
+}
+
+/// Checks that `id` is not used by another message registered into a [`SortedMsgTable`].
+fn check_msg_id<T: Any>(app: &mut App, id: &str) -> Result<(), RegError> {
+    app.world
+        .get_resource_or_insert_with(NetProtocol::default)
+        .check_sorted::<T>(id)
+}
+
+/// A system that forces a sync of a certain component.
+fn send_on_event<T, M>(
+    mut er: EventReader<SyncC<T>>,
+    server: Option<ResMut<Server>>,
+    client: Option<ResMut<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    q: Query<(&NetEntity, &NetComp<T, M>, &T)>,
+) where
+    T: Clone + Into<M> + Component,
+    M: Clone + Into<T> + Any + Send + Sync + Serialize,
+{
+    if er.iter().count() == 0 {
+        return;
+    }
+    trace!("Force Syncing {}", std::any::type_name::<T>());
+
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+    for (net_e, net_c, comp) in q.iter() {
+        // Change detection is ignored, since this is forced.
+        let to_spec = send_target(
+            server.is_some(),
+            host.as_deref(),
+            false,
+            true,
+            net_c.c_dir,
+            net_c.s_dir,
+        );
+        if let Some(to_spec) = to_spec {
+            let msg = NetCompMsg::<M>::new(net_e.id, comp.clone().into());
+            send_msg(
+                server,
+                client,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                to_spec,
+                &msg,
+            );
+        }
+    }
+}
+
+/// A system that sends component `T` using messages of type `M`.
+///
+/// Most of the time, you will call [`sync_comp`](AppExt::sync_comp) which will add this system.
+/// Only add it manually if you know what you are doing and want custom control over when it runs.
 #[allow(clippy::type_complexity)]
 pub fn comp_send<T, M>(
     server: Option<ResMut<Server>>,
     client: Option<ResMut<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
     q: Query<(&NetEntity, &NetComp<T, M>, &T, ChangeTrackers<T>)>,
 ) where
     T: Clone + Into<M> + Component,
-    M: Clone + Into<T> + Any + Send + Sync,
+    M: Clone + Into<T> + Any + Send + Sync + Serialize,
 {
-    if let Some(server) = server {
-        for (net_e, net_c, comp, ct) in q.iter() {
-            // If we are using change detection, and the component hasn't been changed, skip.
-            if net_c.cd && !ct.is_changed() {
-                continue;
-            }
-
-            if let Some(to_spec) = net_c.s_dir.to() {
-                if let Err(e) = server.send_spec(
-                    *to_spec,
-                    &NetCompMsg::<M>::new(net_e.id, comp.clone().into()),
-                ) {
-                    error!("{}", e);
-                }
-            }
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+    for (net_e, net_c, comp, ct) in q.iter() {
+        let to_spec = send_target(
+            server.is_some(),
+            host.as_deref(),
+            net_c.cd,
+            ct.is_changed(),
+            net_c.c_dir,
+            net_c.s_dir,
+        );
+        if let Some(to_spec) = to_spec {
+            let msg = NetCompMsg::<M>::new(net_e.id, comp.clone().into());
+            send_msg(
+                server,
+                client,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                to_spec,
+                &msg,
+            );
         }
-    } else if let Some(client) = client {
-        for (net_e, net_c, comp, ct) in q.iter() {
-            // If we are using change detection, and the component hasn't been changed, skip.
-            if net_c.cd && !ct.is_changed() {
-                continue;
-            }
+    }
+}
 
-            if let CNetDir::To = net_c.c_dir {
-                if let Err(e) = client.send(&NetCompMsg::<M>::new(net_e.id, comp.clone().into())) {
-                    error!("{}", e);
-                }
-            }
+/// Gets who a synced component should be sent to, or `None` if it shouldn't be sent.
+///
+/// This is the direction and change detection logic shared by the send systems. A component is
+/// skipped if `cd` is enabled and it hasn't `changed`, or if the net direction doesn't send it.
+/// On the client, the spec is always [`CIdSpec::All`], since it only sends to the server.
+pub(crate) fn send_target(
+    is_server: bool,
+    host: Option<&Host>,
+    cd: bool,
+    changed: bool,
+    c_dir: CNetDir,
+    s_dir: SNetDir,
+) -> Option<CIdSpec> {
+    // If we are using change detection, and the component hasn't been changed, skip.
+    if cd && !changed {
+        return None;
+    }
+    if is_server {
+        s_dir
+            .to()
+            .copied()
+            .filter(|to_spec| !is_self_send(host, *to_spec))
+    } else if c_dir == CNetDir::To {
+        Some(CIdSpec::All)
+    } else {
+        None
+    }
+}
+
+/// Sends `msg` to `to_spec` on the server, or to the server on the client.
+///
+/// The message is recorded if there is a `recorder`.
+pub(crate) fn send_msg<T: Clone + Any + Send + Sync + Serialize>(
+    server: Option<&Server>,
+    client: Option<&Client>,
+    spectators: Option<&Spectators>,
+    recorder: Option<&Recorder>,
+    to_spec: CIdSpec,
+    msg: &T,
+) {
+    if let Some(server) = server {
+        send_spec(server, spectators, recorder, to_spec, msg);
+    } else if let Some(client) = client {
+        if let Err(e) = client.send(msg) {
+            error!("{}", e);
         }
+        record_sent(recorder, Vec::new, msg);
     }
 }
 
+/// The [`NetRecorder`](crate::replay::NetRecorder) that the sent messages are recorded with.
+#[cfg(feature = "replay")]
+pub(crate) use crate::replay::NetRecorder as Recorder;
+
+/// Takes the place of the [`NetRecorder`](crate::replay::NetRecorder) when the `replay` feature is
+/// disabled, so that the send systems don't need a version without it.
+#[cfg(not(feature = "replay"))]
+#[doc(hidden)]
+#[derive(Resource, Copy, Clone, Debug)]
+pub enum Recorder {}
+
+/// Records `msg` as sent to `cids`, if there is a `recorder`.
+///
+/// `cids` is only called when recording. It is empty on the client, where messages are sent to
+/// the server.
+pub(crate) fn record_sent<T: Serialize>(
+    recorder: Option<&Recorder>,
+    cids: impl FnOnce() -> Vec<CId>,
+    msg: &T,
+) {
+    #[cfg(feature = "replay")]
+    if let Some(recorder) = recorder {
+        recorder.record(RecordDir::Out, cids(), msg);
+    }
+    #[cfg(not(feature = "replay"))]
+    let _ = (recorder, cids, msg);
+}
+
 /// A system that receives messages of type `M` and applies it to component `T`.
 ///
 /// Most of the time, you will call [`sync_comp`](AppExt::sync_comp) which will add this system.
@@ -319,14 +1876,18 @@ pub fn comp_send<T, M>(
 pub fn comp_recv<T, M>(
     server: Option<ResMut<Server>>,
     client: Option<ResMut<Client>>,
+    spectators: Option<Res<Spectators>>,
     mut q: Query<(&NetEntity, &mut NetComp<T, M>, &mut T)>,
 ) where
     T: Clone + Into<M> + Component,
     M: Clone + Into<T> + Any + Send + Sync,
 {
     if let Some(server) = server {
-        // Cache messages
-        let msgs: Vec<NetMsg<NetCompMsg<M>>> = server.recv::<NetCompMsg<M>>().collect();
+        // Cache messages, ignoring the ones from spectators
+        let msgs: Vec<NetMsg<NetCompMsg<M>>> = server
+            .recv::<NetCompMsg<M>>()
+            .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+            .collect();
         for (net_e, mut net_c, mut comp) in q.iter_mut() {
             if let Some(&spec) = net_c.s_dir.from() {
                 if let Some(valid_msg) = get_latest_msg(&msgs, net_c.last, spec, net_e.id) {
@@ -361,7 +1922,7 @@ pub fn comp_recv<T, M>(
 
 /// Helper function that gets the most recent message that matches `from_spec` for entity with `id`
 /// if it is sent later that current.
-fn get_latest_msg<'a, M: Any + Send + Sync>(
+pub(crate) fn get_latest_msg<'a, M: Any + Send + Sync>(
     msgs: &'a [NetMsg<NetCompMsg<M>>],
     current: Option<u32>,
     spec: CIdSpec,
@@ -0,0 +1,289 @@
+//! Syncing components that hold asset handles.
+//!
+//! Handles are local ids, so they can't be sent as they are. Instead, components are converted to
+//! their message type with a [`NetAssetConv`], which has access to the [`AssetServer`] and the
+//! [`NetAssetKeys`], so that handles can be sent as a [`NetHandle`](crate::types::NetHandle) and
+//! resolved back on the receiver.
+//!
+//! ```ignore
+//! #[derive(Component, Clone)]
+//! struct Skin(Handle<Image>);
+//!
+//! app.sync_comp_assets::<Skin, NetHandle<Image>>(&mut table, Transport::TCP);
+//! ```
+
+use crate::app::{get_latest_msg, send_msg, send_target, Recorder};
+use crate::host::Host;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetCompMsg, NetEntity, SNetDir};
+use crate::types::NetHandle;
+use bevy::asset::{Asset, HandleId};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, Uuid};
+use carrier_pigeon::net::{CIdSpec, NetMsg};
+use carrier_pigeon::{Client, Server};
+use serde::Serialize;
+use std::any::Any;
+use std::marker::PhantomData;
+
+/// Stable keys for assets, that are sent instead of the asset path.
+///
+/// This is useful for assets that don't have a path, like ones made with [`Assets::add`], or to
+/// keep the asset paths from being sent. The same keys must be inserted on all peers.
+#[derive(Resource, Debug, Default)]
+pub struct NetAssetKeys {
+    /// The handles, and the type UUID of their asset type.
+    handles: HashMap<String, (HandleUntyped, Uuid)>,
+    keys: HashMap<HandleId, String>,
+}
+
+impl NetAssetKeys {
+    /// Registers `handle` with `key`.
+    ///
+    /// This keeps a strong handle, so the asset stays loaded until it is removed.
+    pub fn insert<T: Asset>(&mut self, key: impl Into<String>, handle: &Handle<T>) {
+        let key = key.into();
+        // Re-keying a handle removes its old key.
+        if let Some(old_key) = self.keys.remove(&handle.id()) {
+            self.handles.remove(&old_key);
+        }
+        if let Some((old, _)) = self
+            .handles
+            .insert(key.clone(), (handle.clone_untyped(), T::TYPE_UUID))
+        {
+            self.keys.remove(&old.id);
+        }
+        self.keys.insert(handle.id(), key);
+    }
+
+    /// Removes the handle registered with `key`.
+    pub fn remove(&mut self, key: &str) -> Option<HandleUntyped> {
+        let (handle, _) = self.handles.remove(key)?;
+        self.keys.remove(&handle.id);
+        Some(handle)
+    }
+
+    /// Gets the handle registered with `key`.
+    pub fn get(&self, key: &str) -> Option<&HandleUntyped> {
+        self.handles.get(key).map(|(handle, _)| handle)
+    }
+
+    /// Gets the handle registered with `key`, if it is a handle to a `T`.
+    ///
+    /// Keys come from other peers, so this returns `None` instead of panicking when the key was
+    /// registered for a different asset type.
+    pub fn get_typed<T: Asset>(&self, key: &str) -> Option<Handle<T>> {
+        match self.handles.get(key) {
+            Some((handle, type_uuid)) if *type_uuid == T::TYPE_UUID => Some(handle.clone().typed()),
+            _ => None,
+        }
+    }
+
+    /// Gets the key of `handle`.
+    pub fn key<T: Asset>(&self, handle: &Handle<T>) -> Option<&str> {
+        self.keys.get(&handle.id()).map(String::as_str)
+    }
+}
+
+/// A conversion between component `T` and a message type, that has access to the assets.
+///
+/// This is used by [`sync_comp_assets`](crate::AppExt::sync_comp_assets) in place of the `Into`
+/// conversions that [`sync_comp`](crate::AppExt::sync_comp) uses.
+pub trait NetAssetConv<T>: Sized {
+    /// Converts the component into the message type.
+    ///
+    /// Returns `None` if it can't be sent, which skips sending it. `assets` is `None` when there
+    /// is no [`AssetServer`], like on a headless server without the `AssetPlugin`.
+    fn from_comp(comp: &T, assets: Option<&AssetServer>, keys: &NetAssetKeys) -> Option<Self>;
+
+    /// Converts the message into the component.
+    ///
+    /// Returns `None` if it can't be resolved, which skips applying it. `assets` is `None` when
+    /// there is no [`AssetServer`].
+    fn into_comp(self, assets: Option<&AssetServer>, keys: &NetAssetKeys) -> Option<T>;
+}
+
+impl<T: Asset> NetAssetConv<Handle<T>> for NetHandle<T> {
+    fn from_comp(
+        comp: &Handle<T>,
+        assets: Option<&AssetServer>,
+        keys: &NetAssetKeys,
+    ) -> Option<Self> {
+        NetHandle::from_handle(comp, assets, keys)
+    }
+
+    fn into_comp(self, assets: Option<&AssetServer>, keys: &NetAssetKeys) -> Option<Handle<T>> {
+        self.to_handle(assets, keys)
+    }
+}
+
+/// A component that tells `bevy-pigeon` to sync the component `T` which is sent as `M`, using a
+/// [`NetAssetConv`].
+///
+/// This is the same as [`NetComp`](crate::sync::NetComp), but for
+/// [`sync_comp_assets`](crate::AppExt::sync_comp_assets).
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NetAssetComp<T, M>
+where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync,
+{
+    /// Change detection.
+    ///
+    /// If enabled, this only sends a message if the component changed. This uses bevy's change
+    /// detection, which may detect false positives.
+    pub cd: bool,
+    /// The timestamp of the last message received and written to this component.
+    pub last: Option<u32>,
+    /// The net direction for the client.
+    pub c_dir: CNetDir,
+    /// The net direction for the server.
+    pub s_dir: SNetDir,
+    _pd: PhantomData<fn() -> (T, M)>,
+}
+
+impl<T, M> Default for NetAssetComp<T, M>
+where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync,
+{
+    fn default() -> Self {
+        NetAssetComp {
+            cd: true,
+            last: None,
+            c_dir: CNetDir::From,
+            s_dir: SNetDir::To(CIdSpec::All),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<T, M> NetAssetComp<T, M>
+where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync,
+{
+    /// Creates a new [`NetAssetComp`] with the given net directions.
+    /// Change detection (cd) defaults to true.
+    pub fn new(cd: bool, c_dir: CNetDir, s_dir: SNetDir) -> Self {
+        NetAssetComp {
+            cd,
+            last: None,
+            c_dir,
+            s_dir,
+            _pd: PhantomData,
+        }
+    }
+}
+
+/// A system that sends component `T` using messages of type `M`, converted with the
+/// [`NetAssetConv`].
+///
+/// Most of the time, you will call [`sync_comp_assets`](crate::AppExt::sync_comp_assets) which
+/// will add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn asset_comp_send<T, M>(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    q: Query<(&NetEntity, &NetAssetComp<T, M>, &T, ChangeTrackers<T>)>,
+) where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync + Serialize,
+{
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+    for (net_e, net_c, comp, ct) in q.iter() {
+        let to_spec = match send_target(
+            server.is_some(),
+            host.as_deref(),
+            net_c.cd,
+            ct.is_changed(),
+            net_c.c_dir,
+            net_c.s_dir,
+        ) {
+            Some(to_spec) => to_spec,
+            None => continue,
+        };
+        match M::from_comp(comp, assets.as_deref(), &keys) {
+            Some(msg) => {
+                let msg = NetCompMsg::<M>::new(net_e.id, msg);
+                send_msg(
+                    server,
+                    client,
+                    spectators.as_deref(),
+                    recorder.as_deref(),
+                    to_spec,
+                    &msg,
+                );
+            }
+            None => warn!(
+                "NetEntity {{ id: {} }} has a `{}` that can't be sent. Its assets need an asset path or a key in `NetAssetKeys`.",
+                net_e.id,
+                std::any::type_name::<T>()
+            ),
+        }
+    }
+}
+
+/// A system that receives messages of type `M` and applies them to component `T`, converted with
+/// the [`NetAssetConv`].
+///
+/// Most of the time, you will call [`sync_comp_assets`](crate::AppExt::sync_comp_assets) which
+/// will add this system.
+pub fn asset_comp_recv<T, M>(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    spectators: Option<Res<Spectators>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    mut q: Query<(&NetEntity, &mut NetAssetComp<T, M>, &mut T)>,
+) where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync,
+{
+    let (msgs, is_server): (Vec<NetMsg<NetCompMsg<M>>>, bool) = if let Some(server) = &server {
+        // Ignore the messages from spectators.
+        let msgs = server
+            .recv::<NetCompMsg<M>>()
+            .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+            .collect();
+        (msgs, true)
+    } else if let Some(client) = &client {
+        (client.recv::<NetCompMsg<M>>().collect(), false)
+    } else {
+        return;
+    };
+
+    for (net_e, mut net_c, mut comp) in q.iter_mut() {
+        let spec = if is_server {
+            match net_c.s_dir.from() {
+                Some(spec) => *spec,
+                None => continue,
+            }
+        } else if net_c.c_dir == CNetDir::From {
+            CIdSpec::All
+        } else {
+            continue;
+        };
+
+        if let Some(valid_msg) = get_latest_msg(&msgs, net_c.last, spec, net_e.id) {
+            net_c.last = valid_msg.time;
+            match valid_msg.msg.clone().into_comp(assets.as_deref(), &keys) {
+                Some(new) => *comp = new,
+                None => warn!(
+                    "Received a `{}` for NetEntity {{ id: {} }} that can't be resolved. Its asset key is not in `NetAssetKeys`, or there is no `AssetServer` to load its path.",
+                    std::any::type_name::<T>(),
+                    net_e.id
+                ),
+            }
+        }
+    }
+}
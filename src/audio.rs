@@ -0,0 +1,234 @@
+//! Replicated audio cues.
+//!
+//! Send a [`NetAudioCue`] event on the server to play a sound on every client, either everywhere,
+//! at a position, or at a [`NetEntity`]. Bevy's [`Audio`] has no spatial audio, so positioned
+//! cues are attenuated by their distance to the [`NetAudioListener`] when they start playing.
+
+use crate::app::Recorder;
+use crate::asset::NetAssetKeys;
+use crate::host::Host;
+use crate::spectator::{send_spec, Spectators};
+use crate::sync::NetEntity;
+use crate::types::{NetAssetRef, NetHandle};
+use bevy::prelude::*;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{Client, Server};
+use serde::{Deserialize, Serialize};
+
+/// Where a [`NetAudioCue`] is played.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum NetAudioPos {
+    /// Played at full volume, wherever the listener is.
+    Global,
+    /// Played at the position of the [`NetEntity`] with this id.
+    Entity(u64),
+    /// Played at this position in the world.
+    Position(Vec3),
+}
+
+/// A networked event that plays a sound.
+///
+/// When sent on the server, it is sent to every client, and played on the host if there is one.
+/// When sent on a client, it is only played locally.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NetAudioCue {
+    /// The sound to play.
+    pub sound: NetHandle<AudioSource>,
+    /// Where to play the sound.
+    pub pos: NetAudioPos,
+    /// The volume, before attenuation.
+    pub volume: f32,
+    /// The playback speed.
+    pub speed: f32,
+}
+
+impl NetAudioCue {
+    /// Creates a new [`NetAudioCue`] that plays the sound at the asset `path` everywhere, at
+    /// full volume and normal speed.
+    pub fn new(path: impl Into<String>) -> Self {
+        NetAudioCue {
+            sound: NetHandle::new(NetAssetRef::Path(path.into())),
+            pos: NetAudioPos::Global,
+            volume: 1.0,
+            speed: 1.0,
+        }
+    }
+
+    /// Plays the sound at `position`.
+    pub fn at(mut self, position: Vec3) -> Self {
+        self.pos = NetAudioPos::Position(position);
+        self
+    }
+
+    /// Plays the sound at the [`NetEntity`] with `id`.
+    pub fn on(mut self, id: u64) -> Self {
+        self.pos = NetAudioPos::Entity(id);
+        self
+    }
+
+    /// Sets the volume.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Sets the playback speed.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// A component that marks the entity that hears the positioned [`NetAudioCue`]s, usually the
+/// camera.
+///
+/// If there is none, positioned cues are played at full volume.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct NetAudioListener;
+
+/// How the volume of positioned [`NetAudioCue`]s falls off with distance.
+///
+/// Within `ref_distance` of the listener, the cue plays at full volume. Further away, the volume
+/// is scaled by `ref_distance / distance`, and cues further than `max_distance` are not played.
+#[derive(Resource, Copy, Clone, PartialEq, Debug)]
+pub struct NetAudioFalloff {
+    /// The distance up to which cues play at full volume.
+    pub ref_distance: f32,
+    /// The distance after which cues are not played.
+    pub max_distance: f32,
+}
+
+impl Default for NetAudioFalloff {
+    fn default() -> Self {
+        NetAudioFalloff {
+            ref_distance: 1.0,
+            max_distance: 100.0,
+        }
+    }
+}
+
+impl NetAudioFalloff {
+    /// The volume multiplier at `distance`, or `None` if the cue is out of range.
+    pub fn gain(&self, distance: f32) -> Option<f32> {
+        if distance > self.max_distance {
+            None
+        } else if distance <= self.ref_distance {
+            Some(1.0)
+        } else {
+            Some(self.ref_distance / distance)
+        }
+    }
+}
+
+/// A system that sends the [`NetAudioCue`] events from the server to every client.
+///
+/// Most of the time, you will call [`sync_audio_cues`](crate::AppExt::sync_audio_cues) which will
+/// add this system.
+pub fn audio_cue_send(
+    server: Option<Res<Server>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    mut er: EventReader<NetAudioCue>,
+) {
+    if let Some(server) = server {
+        for cue in er.iter() {
+            send_spec(
+                &server,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                CIdSpec::All,
+                cue,
+            );
+        }
+    }
+}
+
+/// A system that plays the received [`NetAudioCue`]s, and the ones sent locally.
+///
+/// Local cues are not played on a server without a [`Host`]. Nothing is played without the
+/// [`Audio`] resource, like on a headless server.
+///
+/// Most of the time, you will call [`sync_audio_cues`](crate::AppExt::sync_audio_cues) which will
+/// add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn play_audio_cues(
+    client: Option<Res<Client>>,
+    server: Option<Res<Server>>,
+    host: Option<Res<Host>>,
+    audio: Option<Res<Audio>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    falloff: Res<NetAudioFalloff>,
+    mut er: EventReader<NetAudioCue>,
+    listener: Query<&GlobalTransform, With<NetAudioListener>>,
+    entities: Query<(&NetEntity, &GlobalTransform)>,
+) {
+    let audio = match audio {
+        Some(audio) => audio,
+        None => return,
+    };
+
+    let mut cues: Vec<NetAudioCue> = vec![];
+    if server.is_none() || host.is_some() {
+        cues.extend(er.iter().cloned());
+    }
+    if let Some(client) = client {
+        cues.extend(client.recv::<NetAudioCue>().map(|msg| (*msg).clone()));
+    }
+
+    let listener = listener.iter().next().map(|t| t.translation());
+    for cue in cues {
+        let position = match cue.pos {
+            NetAudioPos::Global => None,
+            NetAudioPos::Position(position) => Some(position),
+            NetAudioPos::Entity(id) => {
+                match entities.iter().find(|(net_e, _)| net_e.id == id) {
+                    Some((_, transform)) => Some(transform.translation()),
+                    // The entity doesn't exist here, so we don't know where to play it.
+                    None => continue,
+                }
+            }
+        };
+        let gain = match (position, listener) {
+            (Some(position), Some(listener)) => match falloff.gain(position.distance(listener)) {
+                Some(gain) => gain,
+                None => continue,
+            },
+            _ => 1.0,
+        };
+
+        let sound = match cue.sound.to_handle(assets.as_deref(), &keys) {
+            Some(sound) => sound,
+            None => {
+                warn!("Received an audio cue that can't be resolved. Its asset key is not in `NetAssetKeys`, or there is no `AssetServer` to load its path.");
+                continue;
+            }
+        };
+        audio.play_with_settings(
+            sound,
+            PlaybackSettings {
+                repeat: false,
+                volume: cue.volume * gain,
+                speed: cue.speed,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain() {
+        let falloff = NetAudioFalloff {
+            ref_distance: 2.0,
+            max_distance: 50.0,
+        };
+        assert_eq!(falloff.gain(0.0), Some(1.0));
+        assert_eq!(falloff.gain(2.0), Some(1.0));
+        assert_eq!(falloff.gain(10.0), Some(0.2));
+        assert_eq!(falloff.gain(50.0), Some(0.04));
+        assert_eq!(falloff.gain(50.1), None);
+    }
+}
@@ -0,0 +1,363 @@
+//! The things needed to sync several components as one message.
+//!
+//! A [`NetBundle`] captures a group of components, sends them in a single message, and applies
+//! them all at once on the receiving end. This keeps the components consistent with each other,
+//! which is not guaranteed when syncing them separately with [`NetComp`](crate::sync::NetComp)s.
+
+use crate::app::{get_latest_msg, send_msg, send_target, Recorder};
+use crate::host::Host;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetCompMsg, NetEntity, SNetDir};
+use crate::SyncC;
+use bevy::ecs::all_tuples;
+use bevy::ecs::query::{QueryItem, ReadOnlyWorldQuery, WorldQuery};
+use bevy::prelude::*;
+use carrier_pigeon::net::{CIdSpec, NetMsg};
+use carrier_pigeon::{Client, Server};
+use serde::Serialize;
+use std::any::Any;
+use std::marker::PhantomData;
+
+/// A component that tells `bevy-pigeon` to sync the components in the tuple `B` which are sent
+/// together as `M`.
+///
+/// `B` is a tuple of components, such as `(Transform, Health, Visibility)`.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NetBundle<B, M>
+where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync,
+{
+    /// Change detection.
+    ///
+    /// If enabled, this only sends a message if any of the components changed. This uses bevy's
+    /// change detection, which may detect false positives.
+    pub cd: bool,
+    /// The timestamp of the last message received and written to these components.
+    pub last: Option<u32>,
+    /// The net direction for the client.
+    pub c_dir: CNetDir,
+    /// The net direction for the server.
+    pub s_dir: SNetDir,
+    _pd: PhantomData<(B, M)>,
+}
+
+impl<B, M> Default for NetBundle<B, M>
+where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync,
+{
+    fn default() -> Self {
+        NetBundle {
+            cd: true,
+            last: None,
+            c_dir: CNetDir::From,
+            s_dir: SNetDir::To(CIdSpec::All),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<B, M> NetBundle<B, M>
+where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync,
+{
+    /// Creates a new [`NetBundle`] with the given net directions.
+    /// Change detection (cd) defaults to true.
+    pub fn new(cd: bool, c_dir: CNetDir, s_dir: SNetDir) -> Self {
+        NetBundle {
+            cd,
+            last: None,
+            c_dir,
+            s_dir,
+            _pd: PhantomData,
+        }
+    }
+}
+
+/// A group of components that can be synced together with a [`NetBundle`].
+///
+/// This is implemented for tuples of up to 8 components that implement [`Clone`].
+pub trait NetBundleComps: Send + Sync + Sized + 'static {
+    /// The query for reading the components.
+    type Ref: ReadOnlyWorldQuery;
+    /// The query for writing to the components.
+    type Mut: WorldQuery;
+    /// The query for the change trackers of the components.
+    type Trackers: ReadOnlyWorldQuery;
+
+    /// Clones the components out of the query item.
+    fn capture(item: QueryItem<'_, Self::Ref>) -> Self;
+
+    /// Returns true if any of the components changed.
+    fn is_changed(trackers: QueryItem<'_, Self::Trackers>) -> bool;
+
+    /// Writes all the components to the query item.
+    fn write(self, item: QueryItem<'_, Self::Mut>);
+}
+
+macro_rules! impl_net_bundle_comps {
+    ($(($c: ident, $v: ident)),*) => {
+        #[allow(non_snake_case)]
+        impl<$($c: Component + Clone),*> NetBundleComps for ($($c,)*) {
+            type Ref = ($(&'static $c,)*);
+            type Mut = ($(&'static mut $c,)*);
+            type Trackers = ($(ChangeTrackers<$c>,)*);
+
+            fn capture(($($v,)*): QueryItem<'_, Self::Ref>) -> Self {
+                ($($v.clone(),)*)
+            }
+
+            fn is_changed(($($v,)*): QueryItem<'_, Self::Trackers>) -> bool {
+                false $(|| $v.is_changed())*
+            }
+
+            fn write(self, ($(mut $v,)*): QueryItem<'_, Self::Mut>) {
+                let ($($c,)*) = self;
+                $(*$v = $c;)*
+            }
+        }
+    };
+}
+
+all_tuples!(impl_net_bundle_comps, 1, 8, C, c);
+
+/// A system that forces a sync of a certain bundle.
+pub(crate) fn send_bundle_on_event<B, M>(
+    mut er: EventReader<SyncC<B>>,
+    server: Option<ResMut<Server>>,
+    client: Option<ResMut<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    q: Query<(&NetEntity, &NetBundle<B, M>, B::Ref)>,
+) where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync + Serialize,
+{
+    if er.iter().count() == 0 {
+        return;
+    }
+    trace!("Force Syncing {}", std::any::type_name::<B>());
+
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+    for (net_e, net_b, comps) in q.iter() {
+        // Change detection is ignored, since this is forced.
+        let to_spec = send_target(
+            server.is_some(),
+            host.as_deref(),
+            false,
+            true,
+            net_b.c_dir,
+            net_b.s_dir,
+        );
+        if let Some(to_spec) = to_spec {
+            let msg = NetCompMsg::<M>::new(net_e.id, B::capture(comps).into());
+            send_msg(
+                server,
+                client,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                to_spec,
+                &msg,
+            );
+        }
+    }
+}
+
+/// A system that sends the components in `B` using messages of type `M`.
+///
+/// Most of the time, you will call [`sync_bundle`](crate::AppExt::sync_bundle) which will add
+/// this system. Only add it manually if you know what you are doing and want custom control over
+/// when it runs.
+#[allow(clippy::type_complexity)]
+pub fn bundle_send<B, M>(
+    server: Option<ResMut<Server>>,
+    client: Option<ResMut<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    q: Query<(&NetEntity, &NetBundle<B, M>, B::Ref, B::Trackers)>,
+) where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync + Serialize,
+{
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        return;
+    }
+    for (net_e, net_b, comps, ct) in q.iter() {
+        let to_spec = send_target(
+            server.is_some(),
+            host.as_deref(),
+            net_b.cd,
+            B::is_changed(ct),
+            net_b.c_dir,
+            net_b.s_dir,
+        );
+        if let Some(to_spec) = to_spec {
+            let msg = NetCompMsg::<M>::new(net_e.id, B::capture(comps).into());
+            send_msg(
+                server,
+                client,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                to_spec,
+                &msg,
+            );
+        }
+    }
+}
+
+/// A system that receives messages of type `M` and applies them to the components in `B`.
+///
+/// Most of the time, you will call [`sync_bundle`](crate::AppExt::sync_bundle) which will add
+/// this system. Only add it manually if you know what you are doing and want custom control over
+/// when it runs.
+pub fn bundle_recv<B, M>(
+    server: Option<ResMut<Server>>,
+    client: Option<ResMut<Client>>,
+    spectators: Option<Res<Spectators>>,
+    mut q: Query<(&NetEntity, &mut NetBundle<B, M>, B::Mut)>,
+) where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync,
+{
+    if let Some(server) = server {
+        // Cache messages, ignoring the ones from spectators
+        let msgs: Vec<NetMsg<NetCompMsg<M>>> = server
+            .recv::<NetCompMsg<M>>()
+            .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+            .collect();
+        for (net_e, mut net_b, comps) in q.iter_mut() {
+            // Warn on overlap
+            if let SNetDir::ToFrom(to_spec, from_spec) = net_b.s_dir {
+                if to_spec.overlaps(from_spec) {
+                    warn!("NetEntity {{ id: {} }} has overlapping `CIdSpec`s in NetDirection::ToFrom. Applying anyway.", net_e.id);
+                }
+            }
+            if let Some(&spec) = net_b.s_dir.from() {
+                if let Some(valid_msg) = get_latest_msg(&msgs, net_b.last, spec, net_e.id) {
+                    net_b.last = valid_msg.time;
+                    let bundle: B = valid_msg.msg.clone().into();
+                    bundle.write(comps);
+                }
+            }
+        }
+    } else if let Some(client) = client {
+        // Cache messages
+        let msgs: Vec<NetMsg<NetCompMsg<M>>> = client.recv::<NetCompMsg<M>>().collect();
+        for (net_e, mut net_b, comps) in q.iter_mut() {
+            if net_b.c_dir == CNetDir::From {
+                if let Some(valid_msg) = get_latest_msg(&msgs, net_b.last, CIdSpec::All, net_e.id) {
+                    net_b.last = valid_msg.time;
+                    let bundle: B = valid_msg.msg.clone().into();
+                    bundle.write(comps);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Pos(f32);
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct PlayerMsg {
+        pos: f32,
+        health: u32,
+    }
+
+    impl From<(Pos, Health)> for PlayerMsg {
+        fn from((pos, health): (Pos, Health)) -> Self {
+            PlayerMsg {
+                pos: pos.0,
+                health: health.0,
+            }
+        }
+    }
+
+    impl From<PlayerMsg> for (Pos, Health) {
+        fn from(o: PlayerMsg) -> Self {
+            (Pos(o.pos), Health(o.health))
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut world = World::new();
+        let sender = world.spawn((Pos(1.5), Health(80))).id();
+        let receiver = world.spawn((Pos(0.0), Health(0))).id();
+
+        let comps = world
+            .query::<<(Pos, Health) as NetBundleComps>::Ref>()
+            .get(&world, sender)
+            .unwrap();
+        let msg = NetCompMsg::<PlayerMsg>::new(5, <(Pos, Health)>::capture(comps).into());
+        // carrier-pigeon sends messages with bincode.
+        let data = bincode::serialize(&msg).unwrap();
+        let msg: NetCompMsg<PlayerMsg> = bincode::deserialize(&data).unwrap();
+        assert_eq!(msg.id, 5);
+
+        let bundle: (Pos, Health) = msg.msg.into();
+        let comps = world
+            .query::<<(Pos, Health) as NetBundleComps>::Mut>()
+            .get_mut(&mut world, receiver)
+            .unwrap();
+        bundle.write(comps);
+        assert_eq!(world.get::<Pos>(receiver), Some(&Pos(1.5)));
+        assert_eq!(world.get::<Health>(receiver), Some(&Health(80)));
+    }
+
+    #[test]
+    fn targets() {
+        let all = SNetDir::To(CIdSpec::All);
+        // Change detection.
+        assert_eq!(
+            send_target(true, None, true, false, CNetDir::From, all),
+            None
+        );
+        assert_eq!(
+            send_target(true, None, true, true, CNetDir::From, all),
+            Some(CIdSpec::All)
+        );
+        assert_eq!(
+            send_target(true, None, false, false, CNetDir::From, all),
+            Some(CIdSpec::All)
+        );
+        // Directions.
+        assert_eq!(
+            send_target(
+                true,
+                None,
+                false,
+                true,
+                CNetDir::To,
+                SNetDir::From(CIdSpec::All)
+            ),
+            None
+        );
+        assert_eq!(
+            send_target(false, None, false, true, CNetDir::From, all),
+            None
+        );
+        assert_eq!(
+            send_target(false, None, false, true, CNetDir::To, all),
+            Some(CIdSpec::All)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Tracking of the entities that are owned by each client.
+//!
+//! When a client disconnects, the entities it owned are despawned on the server, and the despawn
+//! is replicated to the remaining clients.
+
+use crate::app::Recorder;
+use crate::connection::ClientDisconnected;
+use crate::spectator::{send_spec, Spectators};
+use crate::sync::NetEntity;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{CId, Client, Server};
+use serde::{Deserialize, Serialize};
+
+/// A registry of the entities owned by each client.
+///
+/// This is only used on the server. Entities associated with a client are despawned when that
+/// client disconnects, unless they have the [`KeepOnDisconnect`] component.
+#[derive(Resource, Clone, Eq, PartialEq, Debug, Default)]
+pub struct ClientEntities {
+    map: HashMap<CId, Vec<Entity>>,
+}
+
+impl ClientEntities {
+    /// Associates `entity` with the client `cid`.
+    pub fn insert(&mut self, cid: CId, entity: Entity) {
+        let entities = self.map.entry(cid).or_default();
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    }
+
+    /// Removes `entity` from the registry, returning the [`CId`] of the client that owned it.
+    pub fn remove(&mut self, entity: Entity) -> Option<CId> {
+        let (cid, entities) = self.map.iter_mut().find(|(_, es)| es.contains(&entity))?;
+        entities.retain(|e| *e != entity);
+        Some(*cid)
+    }
+
+    /// Removes all the entities associated with the client `cid`, returning them.
+    pub fn take(&mut self, cid: CId) -> Vec<Entity> {
+        self.map.remove(&cid).unwrap_or_default()
+    }
+
+    /// Gets the entities associated with the client `cid`.
+    pub fn entities(&self, cid: CId) -> &[Entity] {
+        self.map.get(&cid).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Gets the [`CId`] of the client that owns `entity`.
+    pub fn owner(&self, entity: Entity) -> Option<CId> {
+        self.map
+            .iter()
+            .find(|(_, es)| es.contains(&entity))
+            .map(|(cid, _)| *cid)
+    }
+}
+
+/// A marker component that opts an entity out of being despawned when its owning client
+/// disconnects.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct KeepOnDisconnect;
+
+/// The message that tells clients to despawn a [`NetEntity`].
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub(crate) struct NetDespawn {
+    pub(crate) id: u64,
+}
+
+/// Despawns the entities owned by clients that disconnected, and tells the remaining clients to
+/// do the same.
+///
+/// Most of the time, you will call
+/// [`despawn_client_entities`](crate::AppExt::despawn_client_entities) which will add this
+/// system.
+pub fn despawn_client_entities(
+    mut commands: Commands,
+    server: Option<Res<Server>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    mut client_entities: ResMut<ClientEntities>,
+    mut er: EventReader<ClientDisconnected>,
+    q: Query<(Option<&NetEntity>, Option<&KeepOnDisconnect>)>,
+) {
+    for discon in er.iter() {
+        for entity in client_entities.take(discon.cid) {
+            let (net_e, keep) = match q.get(entity) {
+                Ok(item) => item,
+                // The entity was already despawned.
+                Err(_) => continue,
+            };
+            if keep.is_some() {
+                continue;
+            }
+
+            commands.entity(entity).despawn_recursive();
+            if let (Some(server), Some(net_e)) = (&server, net_e) {
+                send_spec(
+                    server,
+                    spectators.as_deref(),
+                    recorder.as_deref(),
+                    CIdSpec::All,
+                    &NetDespawn { id: net_e.id },
+                );
+            }
+        }
+    }
+}
+
+/// Despawns the entities that the server told us to.
+///
+/// Most of the time, you will call
+/// [`despawn_client_entities`](crate::AppExt::despawn_client_entities) which will add this
+/// system.
+pub fn recv_despawns(
+    mut commands: Commands,
+    client: Option<Res<Client>>,
+    q: Query<(Entity, &NetEntity)>,
+) {
+    if let Some(client) = client {
+        for msg in client.recv::<NetDespawn>() {
+            for (entity, net_e) in q.iter().filter(|(_, net_e)| net_e.id == msg.id) {
+                trace!("Despawning NetEntity {{ id: {} }}", net_e.id);
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use carrier_pigeon::net::Status;
+
+    #[test]
+    fn despawn_on_disconnect() {
+        let mut app = App::new();
+        app.add_event::<ClientDisconnected>()
+            .init_resource::<ClientEntities>()
+            .add_system(despawn_client_entities);
+
+        let owned = app.world.spawn(NetEntity::new(1)).id();
+        let kept = app.world.spawn((NetEntity::new(2), KeepOnDisconnect)).id();
+        let other = app.world.spawn(NetEntity::new(3)).id();
+        let mut client_entities = app.world.resource_mut::<ClientEntities>();
+        client_entities.insert(1, owned);
+        client_entities.insert(1, kept);
+        client_entities.insert(2, other);
+
+        app.world.send_event(ClientDisconnected {
+            cid: 1,
+            status: Status::Closed,
+        });
+        app.update();
+
+        assert!(app.world.get_entity(owned).is_none());
+        assert!(app.world.get_entity(kept).is_some());
+        assert!(app.world.get_entity(other).is_some());
+        let client_entities = app.world.resource::<ClientEntities>();
+        assert!(client_entities.entities(1).is_empty());
+        assert_eq!(client_entities.entities(2), &[other]);
+    }
+}
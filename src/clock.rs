@@ -0,0 +1,240 @@
+//! Clock synchronization.
+//!
+//! The client periodically pings the server with its own time, and the server replies with its
+//! time. From these, the client estimates the round trip time and the offset between its clock and
+//! the server's, which are exposed through the [`ServerTime`] resource.
+//!
+//! Both clocks are [`Time::elapsed_seconds_f64`]. This is the same clock that
+//! [`CompHistory`](crate::history::CompHistory) uses, so a client can send
+//! [`ServerTime::now`] along with an action, for the server to [`rewind`](crate::history::rewind)
+//! to.
+//!
+//! The send time of the messages (`NetMsg::time`) isn't used, since it is a different clock: it
+//! is only meant to order the messages, and isn't comparable to [`Time`]. Sending the [`Time`]
+//! in the pings keeps the estimate in the clock that the rest of the app uses.
+
+use crate::app::{record_sent, Recorder};
+use bevy::prelude::*;
+use carrier_pigeon::{CId, Client, Server};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// The number of samples that the estimate is made from.
+const SAMPLES: usize = 8;
+
+/// The client's estimate of the server's clock.
+///
+/// This is inserted by [`sync_clock`](crate::AppExt::sync_clock), and updated on the client
+/// whenever the server replies to a ping. On the server, [`now`](ServerTime::now) is just the
+/// local time.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct ServerTime {
+    interval: Duration,
+    /// The recent samples, as `(rtt, offset)`.
+    samples: VecDeque<(f64, f64)>,
+    offset: f64,
+    rtt: f64,
+}
+
+impl ServerTime {
+    /// Creates a new [`ServerTime`] that pings the server every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        ServerTime {
+            interval,
+            samples: VecDeque::with_capacity(SAMPLES),
+            offset: 0.0,
+            rtt: 0.0,
+        }
+    }
+
+    /// The estimated time on the server, in seconds.
+    ///
+    /// This is comparable to [`Time::elapsed_seconds_f64`] on the server.
+    pub fn now(&self, time: &Time) -> f64 {
+        self.to_server(time.elapsed_seconds_f64())
+    }
+
+    /// Converts a local time to the estimated server time, both in seconds.
+    pub fn to_server(&self, local: f64) -> f64 {
+        local + self.offset
+    }
+
+    /// Converts a server time to the estimated local time, both in seconds.
+    pub fn to_local(&self, server: f64) -> f64 {
+        server - self.offset
+    }
+
+    /// The estimated offset of the server's clock from ours, in seconds.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// The estimated round trip time.
+    pub fn rtt(&self) -> Duration {
+        Duration::from_secs_f64(self.rtt)
+    }
+
+    /// The time between pings.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns true once the server has replied to at least one ping.
+    pub fn is_synced(&self) -> bool {
+        !self.samples.is_empty()
+    }
+
+    /// Forgets the samples, starting the estimate over.
+    ///
+    /// This is done automatically when the client is replaced.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.offset = 0.0;
+        self.rtt = 0.0;
+    }
+
+    /// Adds a sample and updates the estimate.
+    ///
+    /// The offset is taken from the sample with the lowest round trip time, since it has the least
+    /// room for asymmetric delays. The round trip time is the average.
+    fn add_sample(&mut self, rtt: f64, offset: f64) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((rtt, offset));
+
+        self.rtt = self.samples.iter().map(|(rtt, _)| rtt).sum::<f64>() / self.samples.len() as f64;
+        if let Some((_, offset)) = self
+            .samples
+            .iter()
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+        {
+            self.offset = *offset;
+        }
+    }
+}
+
+/// The message that the client pings the server with.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub(crate) struct ClockPing {
+    pub(crate) client_time: f64,
+}
+
+/// The message that the server replies to a [`ClockPing`] with.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub(crate) struct ClockPong {
+    pub(crate) client_time: f64,
+    pub(crate) server_time: f64,
+}
+
+/// Pings the server every [`interval`](ServerTime::interval), and updates the [`ServerTime`]
+/// from the replies.
+///
+/// Most of the time, you will call [`sync_clock`](crate::AppExt::sync_clock) which will add this
+/// system.
+pub fn client_clock_sync(
+    client: Option<Res<Client>>,
+    recorder: Option<Res<Recorder>>,
+    time: Res<Time>,
+    mut server_time: ResMut<ServerTime>,
+    mut last_ping: Local<Option<f64>>,
+) {
+    let client = match client {
+        Some(client) => client,
+        None => return,
+    };
+    let now = time.elapsed_seconds_f64();
+    if client.is_added() {
+        server_time.reset();
+        *last_ping = None;
+    }
+
+    for msg in client.recv::<ClockPong>() {
+        let rtt = (now - msg.client_time).max(0.0);
+        let offset = msg.server_time + rtt / 2.0 - now;
+        server_time.add_sample(rtt, offset);
+    }
+
+    let due = last_ping.map_or(true, |last| {
+        now - last >= server_time.interval.as_secs_f64()
+    });
+    if due {
+        *last_ping = Some(now);
+        let ping = ClockPing { client_time: now };
+        if let Err(e) = client.send(&ping) {
+            error!("{}", e);
+        }
+        record_sent(recorder.as_deref(), Vec::new, &ping);
+    }
+}
+
+/// Replies to the clients' pings with the server's time.
+///
+/// Most of the time, you will call [`sync_clock`](crate::AppExt::sync_clock) which will add this
+/// system.
+pub fn server_clock_sync(
+    server: Option<Res<Server>>,
+    recorder: Option<Res<Recorder>>,
+    time: Res<Time>,
+) {
+    if let Some(server) = server {
+        let server_time = time.elapsed_seconds_f64();
+        let pings: Vec<(CId, f64)> = server
+            .recv::<ClockPing>()
+            .map(|msg| (msg.cid, msg.client_time))
+            .collect();
+        for (cid, client_time) in pings {
+            let pong = ClockPong {
+                client_time,
+                server_time,
+            };
+            if let Err(e) = server.send_to(cid, &pong) {
+                error!("{}", e);
+            }
+            record_sent(recorder.as_deref(), || vec![cid], &pong);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_rtt_offset() {
+        let mut server_time = ServerTime::new(Duration::from_secs(1));
+        assert!(!server_time.is_synced());
+        server_time.add_sample(0.2, 3.0);
+        server_time.add_sample(0.05, 2.5);
+        server_time.add_sample(0.1, 2.0);
+
+        assert!(server_time.is_synced());
+        // The offset is from the sample with the lowest round trip time.
+        assert_eq!(server_time.offset(), 2.5);
+        // The round trip time is the average.
+        assert!((server_time.rtt - 0.35 / 3.0).abs() < 1e-9);
+        assert_eq!(server_time.to_server(10.0), 12.5);
+        assert_eq!(server_time.to_local(12.5), 10.0);
+    }
+
+    #[test]
+    fn sample_window() {
+        let mut server_time = ServerTime::new(Duration::from_secs(1));
+        server_time.add_sample(0.01, 5.0);
+        for _ in 0..SAMPLES - 1 {
+            server_time.add_sample(0.1, 1.0);
+        }
+        assert_eq!(server_time.offset(), 5.0);
+
+        // The first sample leaves the window.
+        server_time.add_sample(0.1, 1.0);
+        assert_eq!(server_time.samples.len(), SAMPLES);
+        assert_eq!(server_time.offset(), 1.0);
+        assert!((server_time.rtt - 0.1).abs() < 1e-9);
+
+        server_time.reset();
+        assert!(!server_time.is_synced());
+        assert_eq!(server_time.offset(), 0.0);
+    }
+}
@@ -0,0 +1,226 @@
+//! Configuration for where and when the networking systems run.
+
+use crate::state::net_connected;
+use crate::NetLabel;
+use bevy::ecs::schedule::{ShouldRun, StageLabelId, SystemDescriptor};
+use bevy::ecs::system::BoxedSystem;
+use bevy::prelude::*;
+use bevy::time::FixedTimestep;
+
+/// Configures the stages and run criteria of the networking systems.
+///
+/// This is given to the [`ClientPlugin`](crate::ClientPlugin) and
+/// [`ServerPlugin`](crate::ServerPlugin), which insert it as a resource. The systems added by
+/// [`AppExt`](crate::AppExt) use the [`NetConfig`] resource, so the plugins must be added (or the
+/// resource inserted) **before** calling [`sync_comp`](crate::AppExt::sync_comp) and friends, or
+/// the default config is used.
+///
+/// The systems that receive messages are labeled with [`NetLabel::Recv`], and the systems that
+/// send messages are labeled with [`NetLabel::Send`].
+#[derive(Resource, Copy, Clone, Debug)]
+pub struct NetConfig {
+    /// The stage that the systems that receive messages are added to.
+    ///
+    /// Defaults to [`CoreStage::First`].
+    pub recv_stage: StageLabelId,
+    /// The stage that the systems that send messages are added to.
+    ///
+    /// Defaults to [`CoreStage::Last`].
+    pub send_stage: StageLabelId,
+    /// The run criteria for the systems that sync components.
+    ///
+    /// This does not apply to the systems that receive new messages and handle connections;
+    /// those always run.
+    ///
+    /// Defaults to [`NetRunCriteria::Connected`].
+    pub run_criteria: NetRunCriteria,
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        NetConfig {
+            recv_stage: CoreStage::First.as_label(),
+            send_stage: CoreStage::Last.as_label(),
+            run_criteria: NetRunCriteria::Connected,
+        }
+    }
+}
+
+impl NetConfig {
+    /// Creates a new [`NetConfig`] with the given stages and run criteria.
+    pub fn new(
+        recv_stage: impl StageLabel,
+        send_stage: impl StageLabel,
+        run_criteria: NetRunCriteria,
+    ) -> Self {
+        NetConfig {
+            recv_stage: recv_stage.as_label(),
+            send_stage: send_stage.as_label(),
+            run_criteria,
+        }
+    }
+
+    /// Gets the [`NetConfig`] resource from `app`, or the default if there is none.
+    pub(crate) fn get(app: &App) -> Self {
+        app.world
+            .get_resource::<NetConfig>()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Adds a system that receives messages, using this config.
+    pub(crate) fn add_recv_system<Params>(
+        &self,
+        app: &mut App,
+        system: impl IntoSystemDescriptor<Params>,
+    ) {
+        app.add_system_to_stage(
+            self.recv_stage,
+            self.run_criteria.apply(system).label(NetLabel::Recv),
+        );
+    }
+
+    /// Adds a system that sends messages, using this config.
+    pub(crate) fn add_send_system<Params>(
+        &self,
+        app: &mut App,
+        system: impl IntoSystemDescriptor<Params>,
+    ) {
+        app.add_system_to_stage(
+            self.send_stage,
+            self.run_criteria.apply(system).label(NetLabel::Send),
+        );
+    }
+}
+
+/// The run criteria for the networking systems.
+#[derive(Copy, Clone, Debug)]
+pub enum NetRunCriteria {
+    /// Always run.
+    Always,
+    /// Only run in the [`NetState::Connected`](crate::NetState::Connected) state.
+    ///
+    /// If the [`NetStatePlugin`](crate::NetStatePlugin) is not added, this always runs.
+    Connected,
+    /// Run on a fixed timestep, with the given step in seconds.
+    ///
+    /// Every system gets its own timer.
+    FixedTimestep(f64),
+    /// Run with a custom run criteria.
+    ///
+    /// The function is called once for each system to make its run criteria.
+    Custom(fn() -> BoxedSystem<(), ShouldRun>),
+}
+
+impl NetRunCriteria {
+    /// Applies this run criteria to `system`.
+    fn apply<Params>(&self, system: impl IntoSystemDescriptor<Params>) -> SystemDescriptor {
+        match *self {
+            NetRunCriteria::Always => system.into_descriptor(),
+            NetRunCriteria::Connected => system.with_run_criteria(net_connected),
+            NetRunCriteria::FixedTimestep(step) => {
+                system.with_run_criteria(FixedTimestep::step(step))
+            }
+            NetRunCriteria::Custom(criteria) => system.with_run_criteria(criteria()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NetState;
+    use bevy::time::FixedTimesteps;
+    use std::time::{Duration, Instant};
+
+    #[derive(Resource, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log_recv(mut log: ResMut<Log>) {
+        log.0.push("recv");
+    }
+
+    fn log_send(mut log: ResMut<Log>) {
+        log.0.push("send");
+    }
+
+    fn log_update(mut log: ResMut<Log>) {
+        log.0.push("update");
+    }
+
+    fn never() -> BoxedSystem<(), ShouldRun> {
+        Box::new(IntoSystem::into_system(|| ShouldRun::No))
+    }
+
+    /// Makes an app with a recv and send system added with `config`.
+    fn app(config: NetConfig) -> App {
+        let mut app = App::new();
+        app.init_resource::<Log>().add_system(log_update);
+        config.add_recv_system(&mut app, log_recv);
+        config.add_send_system(&mut app, log_send);
+        app
+    }
+
+    fn log(app: &App) -> &[&'static str] {
+        &app.world.resource::<Log>().0
+    }
+
+    #[test]
+    fn custom_stages() {
+        let config = NetConfig::new(
+            CoreStage::PostUpdate,
+            CoreStage::PreUpdate,
+            NetRunCriteria::Always,
+        );
+        let mut app = app(config);
+        app.update();
+        assert_eq!(log(&app), ["send", "update", "recv"]);
+    }
+
+    #[test]
+    fn connected_criteria() {
+        let config = NetConfig::default();
+        let mut app = app(config);
+        app.insert_resource(State::new(NetState::Disconnected));
+        app.update();
+        assert_eq!(log(&app), ["update"]);
+
+        app.insert_resource(State::new(NetState::Connected));
+        app.update();
+        assert_eq!(log(&app), ["update", "recv", "update", "send"]);
+    }
+
+    #[test]
+    fn fixed_timestep_criteria() {
+        let config = NetConfig::new(
+            CoreStage::First,
+            CoreStage::Last,
+            NetRunCriteria::FixedTimestep(1.0),
+        );
+        let mut app = app(config);
+        let start = Instant::now();
+        let mut time = Time::default();
+        time.update_with_instant(start);
+        app.insert_resource(time).init_resource::<FixedTimesteps>();
+        app.update();
+        assert_eq!(log(&app), ["update"]);
+
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_secs_f64(1.5));
+        app.update();
+        assert_eq!(log(&app), ["update", "recv", "update", "send"]);
+    }
+
+    #[test]
+    fn custom_criteria() {
+        let config = NetConfig::new(
+            CoreStage::First,
+            CoreStage::Last,
+            NetRunCriteria::Custom(never),
+        );
+        let mut app = app(config);
+        app.update();
+        assert_eq!(log(&app), ["update"]);
+    }
+}
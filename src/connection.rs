@@ -0,0 +1,291 @@
+//! Connection lifecycle events and the systems that fire them.
+
+use crate::host::Host;
+use bevy::prelude::*;
+use carrier_pigeon::net::Status;
+use carrier_pigeon::{CId, Client, OptionPendingClient, Server};
+use std::any::Any;
+use std::io;
+
+/// An event that is fired on the server when a new client's connection is accepted.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ClientConnected<C> {
+    /// The connection ID given to the new client.
+    pub cid: CId,
+    /// The connection message that the client sent with its request.
+    pub request: C,
+}
+
+/// An event that is fired on the server when a client disconnects.
+#[derive(Debug)]
+pub struct ClientDisconnected {
+    /// The connection ID of the client that disconnected.
+    pub cid: CId,
+    /// The reason the client disconnected.
+    pub status: Status,
+}
+
+/// An event that is fired on the client when a pending connection is accepted by the server.
+///
+/// Whether a response is an acceptance is decided by the `accept` function of the
+/// [`ClientPlugin`](crate::ClientPlugin), which accepts every response by default.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConnectedToServer<R> {
+    /// The response message that the server sent.
+    pub response: R,
+}
+
+/// An event that is fired on the client when a pending connection is rejected by the server.
+///
+/// The `Client` resource is not inserted when this happens.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ConnectionRejected<R> {
+    /// The response message that the server sent.
+    pub response: R,
+}
+
+/// Decides whether a response from the server is an acceptance.
+///
+/// This is inserted by the [`ClientPlugin`](crate::ClientPlugin).
+#[derive(Resource)]
+pub(crate) struct ClientAccept<R>(pub(crate) fn(&R) -> bool);
+
+/// An event that is fired on the client when a pending connection fails before getting a
+/// response from the server.
+#[derive(Debug)]
+pub struct ConnectionFailed {
+    /// The error that occurred while connecting.
+    pub error: io::Error,
+}
+
+/// An event that is fired on the client when the connection to the server is closed.
+///
+/// The [`Client`] resource is left in place so that you can inspect its
+/// [`status`](Client::status).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct DisconnectedFromServer;
+
+/// The policy that decides whether new connection requests are accepted or rejected.
+///
+/// Insert this as a resource on the server. If it is not present, the [`ServerPlugin`] will not
+/// handle new connections, and you may handle them yourself.
+///
+/// [`ServerPlugin`]: crate::ServerPlugin
+#[derive(Resource)]
+pub struct ConnectionPolicy<C, R> {
+    hook: Box<dyn FnMut(CId, &C) -> (bool, R) + Send + Sync>,
+    max: Option<(usize, R)>,
+}
+
+impl<C, R> ConnectionPolicy<C, R>
+where
+    C: Any + Send + Sync,
+    R: Any + Send + Sync + Clone,
+{
+    /// Creates a new [`ConnectionPolicy`] that uses `hook` to validate connection requests.
+    ///
+    /// `hook` returns whether to accept the connection, and the response to send back, just like
+    /// the hook given to [`Server::handle_new_cons`].
+    pub fn new(hook: impl FnMut(CId, &C) -> (bool, R) + Send + Sync + 'static) -> Self {
+        ConnectionPolicy {
+            hook: Box::new(hook),
+            max: None,
+        }
+    }
+
+    /// Creates a new [`ConnectionPolicy`] that accepts every connection request, responding with
+    /// the result of `response`.
+    pub fn accept_all(response: impl Fn(CId) -> R + Send + Sync + 'static) -> Self {
+        Self::new(move |cid, _| (true, response(cid)))
+    }
+
+    /// Rejects any connection request once there are `max` clients connected, responding with
+    /// `response`.
+    pub fn with_max_connections(mut self, max: usize, response: R) -> Self {
+        self.max = Some((max, response));
+        self
+    }
+
+    /// Decides whether to accept a connection request.
+    fn validate(&mut self, cid: CId, request: &C, connected: usize) -> (bool, R) {
+        if let Some((max, response)) = &self.max {
+            if connected >= *max {
+                return (false, response.clone());
+            }
+        }
+        (self.hook)(cid, request)
+    }
+}
+
+impl<C, R> std::fmt::Debug for ConnectionPolicy<C, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionPolicy")
+            .field("max", &self.max.as_ref().map(|(max, _)| max))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Handles new connections using the [`ConnectionPolicy`], firing a [`ClientConnected`] event for
+/// every accepted connection.
+///
+/// This is added by the [`ServerPlugin`](crate::ServerPlugin).
+pub fn handle_new_cons<C, R>(
+    server: Option<ResMut<Server>>,
+    policy: Option<ResMut<ConnectionPolicy<C, R>>>,
+    host: Option<Res<Host>>,
+    mut ew: EventWriter<ClientConnected<C>>,
+) where
+    C: Any + Send + Sync,
+    R: Any + Send + Sync + Clone,
+{
+    let (mut server, mut policy) = match (server, policy) {
+        (Some(server), Some(policy)) => (server, policy),
+        _ => return,
+    };
+
+    let mut connected = server.cids().count();
+    server.handle_new_cons(|cid, request: C| {
+        let (accept, response) = policy.validate(cid, &request, connected);
+        if accept {
+            if host.as_ref().map_or(false, |host| host.cid == cid) {
+                error!(
+                    "Client {} has the same CId as the `Host`. Use `Host::new` with a CId that is never given out.",
+                    cid
+                );
+            }
+            connected += 1;
+            ew.send(ClientConnected { cid, request });
+        }
+        (accept, response)
+    });
+}
+
+/// Handles disconnects, firing a [`ClientDisconnected`] event for every one.
+///
+/// This is added by the [`ServerPlugin`](crate::ServerPlugin).
+pub fn handle_disconnects(server: Option<ResMut<Server>>, mut ew: EventWriter<ClientDisconnected>) {
+    if let Some(mut server) = server {
+        server.handle_disconnects(|cid, status| {
+            ew.send(ClientDisconnected { cid, status });
+        });
+    }
+}
+
+/// Polls the [`OptionPendingClient`] resource once the server responds. If the response is an
+/// acceptance, this inserts the [`Client`] resource and fires a [`ConnectedToServer`] event.
+/// Otherwise, this fires a [`ConnectionRejected`] event.
+///
+/// This is added by the [`ClientPlugin`](crate::ClientPlugin).
+pub fn poll_pending_client<R>(
+    mut commands: Commands,
+    pending: Option<ResMut<OptionPendingClient>>,
+    accept: Option<Res<ClientAccept<R>>>,
+    mut ew_connected: EventWriter<ConnectedToServer<R>>,
+    mut ew_rejected: EventWriter<ConnectionRejected<R>>,
+    mut ew_failed: EventWriter<ConnectionFailed>,
+) where
+    R: Any + Send + Sync,
+{
+    let mut pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+    if pending.done() != Some(true) {
+        return;
+    }
+
+    match pending.take::<R>() {
+        Some(Ok((client, response))) => {
+            if accept.map_or(true, |accept| (accept.0)(&response)) {
+                commands.insert_resource(client);
+                ew_connected.send(ConnectedToServer { response });
+            } else {
+                // The client is dropped here, which closes the connection.
+                ew_rejected.send(ConnectionRejected { response });
+            }
+        }
+        Some(Err(error)) => ew_failed.send(ConnectionFailed { error }),
+        None => {}
+    }
+    commands.remove_resource::<OptionPendingClient>();
+}
+
+/// Fires a [`DisconnectedFromServer`] event when the client's connection closes.
+///
+/// This is added by the [`ClientPlugin`](crate::ClientPlugin).
+pub fn check_client_status(
+    client: Option<Res<Client>>,
+    mut was_open: Local<bool>,
+    mut ew: EventWriter<DisconnectedFromServer>,
+) {
+    let client = match client {
+        Some(client) => client,
+        None => {
+            *was_open = false;
+            return;
+        }
+    };
+
+    if client.is_added() {
+        *was_open = true;
+    }
+    if *was_open && !client.open() {
+        *was_open = false;
+        ew.send(DisconnectedFromServer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    enum Response {
+        Accepted,
+        Rejected,
+        Full,
+    }
+
+    #[test]
+    fn policy_hook() {
+        let mut policy = ConnectionPolicy::new(|_, pass: &String| {
+            if pass == "secret" {
+                (true, Response::Accepted)
+            } else {
+                (false, Response::Rejected)
+            }
+        });
+        assert_eq!(
+            policy.validate(1, &"secret".to_owned(), 0),
+            (true, Response::Accepted)
+        );
+        assert_eq!(
+            policy.validate(2, &"guess".to_owned(), 0),
+            (false, Response::Rejected)
+        );
+    }
+
+    #[test]
+    fn policy_accept_all() {
+        let mut policy = ConnectionPolicy::<(), CId>::accept_all(|cid| cid);
+        assert_eq!(policy.validate(3, &(), 100), (true, 3));
+    }
+
+    #[test]
+    fn policy_max_connections() {
+        let mut policy = ConnectionPolicy::<(), Response>::accept_all(|_| Response::Accepted)
+            .with_max_connections(2, Response::Full);
+        assert_eq!(policy.validate(1, &(), 0), (true, Response::Accepted));
+        assert_eq!(policy.validate(2, &(), 1), (true, Response::Accepted));
+        assert_eq!(policy.validate(3, &(), 2), (false, Response::Full));
+        assert_eq!(policy.validate(4, &(), 5), (false, Response::Full));
+    }
+
+    #[test]
+    fn policy_max_connections_first() {
+        // The hook is not called once the server is full.
+        let mut policy = ConnectionPolicy::<(), Response>::new(|_, _| panic!("hook was called"))
+            .with_max_connections(0, Response::Full);
+        assert_eq!(policy.validate(1, &(), 0), (false, Response::Full));
+    }
+}
@@ -0,0 +1,218 @@
+//! Server-side lag compensation.
+//!
+//! When a client acts, it sees the other entities where they were a little while ago. To check
+//! what it actually saw (for hit detection, for example), the server keeps a history of the
+//! values of chosen components, and can temporarily [`rewind`] them to the time that the client
+//! perceived.
+
+use crate::sync::NetEntity;
+use bevy::prelude::*;
+use carrier_pigeon::Server;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+/// The recent values of component `T` of a [`NetEntity`], keyed by the elapsed time in
+/// seconds ([`Time::elapsed_seconds_f64`]).
+///
+/// This is added and filled in on the server for the types passed to
+/// [`track_history`](crate::AppExt::track_history).
+#[derive(Component, Clone, Debug)]
+pub struct CompHistory<T> {
+    entries: VecDeque<(f64, T)>,
+    max_age: f64,
+}
+
+impl<T> CompHistory<T> {
+    /// Creates a new, empty [`CompHistory`] that keeps values for `max_age`.
+    pub fn new(max_age: Duration) -> Self {
+        CompHistory {
+            entries: VecDeque::new(),
+            max_age: max_age.as_secs_f64(),
+        }
+    }
+
+    /// Adds the value at `time`, and drops the values older than the max age.
+    pub fn push(&mut self, time: f64, value: T) {
+        self.entries.push_back((time, value));
+        while self
+            .entries
+            .front()
+            .map_or(false, |(t, _)| time - *t > self.max_age)
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Gets the value at `time`.
+    ///
+    /// This is the latest value recorded at or before `time`. If `time` is older than the
+    /// history, this is the oldest value.
+    pub fn at(&self, time: f64) -> Option<&T> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= time)
+            .or_else(|| self.entries.front())
+            .map(|(_, value)| value)
+    }
+
+    /// Iterates over the recorded values and their times, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &(f64, T)> {
+        self.entries.iter()
+    }
+
+    /// Clears the history.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// The max age of the history of component `T`.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub(crate) struct HistoryMaxAge<T> {
+    pub(crate) max_age: Duration,
+    _pd: PhantomData<fn() -> T>,
+}
+
+impl<T> HistoryMaxAge<T> {
+    pub(crate) fn new(max_age: Duration) -> Self {
+        HistoryMaxAge {
+            max_age,
+            _pd: PhantomData,
+        }
+    }
+}
+
+/// Records the current value of component `T` of every [`NetEntity`] into its
+/// [`CompHistory<T>`], adding it if needed.
+///
+/// This only runs on the server. This is added by [`track_history`](crate::AppExt::track_history).
+#[allow(clippy::type_complexity)]
+pub(crate) fn record_history<T: Component + Clone>(
+    mut commands: Commands,
+    server: Option<Res<Server>>,
+    time: Res<Time>,
+    max_age: Res<HistoryMaxAge<T>>,
+    mut q: Query<(Entity, &T, Option<&mut CompHistory<T>>), With<NetEntity>>,
+) {
+    if server.is_none() {
+        return;
+    }
+    let now = time.elapsed_seconds_f64();
+    for (entity, comp, history) in q.iter_mut() {
+        match history {
+            Some(mut history) => history.push(now, comp.clone()),
+            None => {
+                let mut history = CompHistory::new(max_age.max_age);
+                history.push(now, comp.clone());
+                commands.entity(entity).insert(history);
+            }
+        }
+    }
+}
+
+/// Rewinds component `T` of every entity with a [`CompHistory<T>`] to its value at `time`, runs
+/// `f`, then restores the current values.
+///
+/// `time` is in the same clock as [`Time::elapsed_seconds_f64`] on the server. The change
+/// detection is bypassed, so rewinding doesn't cause the components to be sent. If `f` panics,
+/// the current values are restored before the panic continues.
+///
+/// To rewind several components, nest the calls:
+/// ```ignore
+/// rewind::<Transform, _>(world, time, |world| {
+///     rewind::<Hitbox, _>(world, time, |world| check_hit(world, ray))
+/// })
+/// ```
+pub fn rewind<T, R>(world: &mut World, time: f64, f: impl FnOnce(&mut World) -> R) -> R
+where
+    T: Component + Clone,
+{
+    let mut q = world.query::<(Entity, &mut T, &CompHistory<T>)>();
+    let mut saved = vec![];
+    for (entity, mut comp, history) in q.iter_mut(world) {
+        if let Some(past) = history.at(time) {
+            saved.push((entity, comp.clone()));
+            *comp.bypass_change_detection() = past.clone();
+        }
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(world)));
+
+    for (entity, value) in saved {
+        if let Some(mut comp) = world.get_mut::<T>(entity) {
+            *comp.bypass_change_detection() = value;
+        }
+    }
+    result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Pos(f32);
+
+    fn history() -> CompHistory<f32> {
+        let mut history = CompHistory::new(Duration::from_secs(1));
+        history.push(1.0, 10.0);
+        history.push(1.5, 15.0);
+        history.push(2.0, 20.0);
+        history
+    }
+
+    #[test]
+    fn at() {
+        let history = history();
+        // Before the first sample.
+        assert_eq!(history.at(0.5), Some(&10.0));
+        // At and between samples.
+        assert_eq!(history.at(1.0), Some(&10.0));
+        assert_eq!(history.at(1.7), Some(&15.0));
+        // After the last sample.
+        assert_eq!(history.at(5.0), Some(&20.0));
+
+        assert_eq!(
+            CompHistory::<f32>::new(Duration::from_secs(1)).at(1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn push_prunes() {
+        let mut history = history();
+        history.push(2.4, 24.0);
+        let times: Vec<_> = history.iter().map(|(t, _)| *t).collect();
+        assert_eq!(times, vec![1.5, 2.0, 2.4]);
+    }
+
+    fn world() -> (World, Entity) {
+        let mut world = World::new();
+        let mut history = CompHistory::new(Duration::from_secs(1));
+        history.push(1.0, Pos(10.0));
+        history.push(2.0, Pos(20.0));
+        let entity = world.spawn((Pos(30.0), history)).id();
+        (world, entity)
+    }
+
+    #[test]
+    fn rewind_restores() {
+        let (mut world, entity) = world();
+        let seen = rewind::<Pos, _>(&mut world, 1.2, |world| world.get::<Pos>(entity).cloned());
+        assert_eq!(seen, Some(Pos(10.0)));
+        assert_eq!(world.get::<Pos>(entity), Some(&Pos(30.0)));
+    }
+
+    #[test]
+    fn rewind_restores_on_panic() {
+        let (mut world, entity) = world();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            rewind::<Pos, ()>(&mut world, 1.2, |_| panic!("hit check failed"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(world.get::<Pos>(entity), Some(&Pos(30.0)));
+    }
+}
@@ -0,0 +1,78 @@
+//! Listen-server (host) mode.
+//!
+//! A host runs the server and plays the game at the same time. Instead of connecting a client to
+//! its own server through a loopback socket, the host's local player is treated as a local client
+//! with the [`CId`] stored in the [`Host`] resource.
+
+use bevy::prelude::*;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::CId;
+
+/// The [`CId`] used for the host's local player by default.
+///
+/// `carrier-pigeon` gives out connection IDs starting at 1, so this doesn't collide with a remote
+/// client. This is checked when clients connect, and an error is logged if a client is given the
+/// host's [`CId`]. In that case, use [`Host::new`] with a [`CId`] that is never given out.
+pub const HOST_CID: CId = 0;
+
+/// Marks this instance as a host.
+///
+/// Insert this resource along with the `Server` resource, and **don't** create a `Client`. The
+/// host's own [`NetComp`](crate::sync::NetComp)s should use the server directions, with the
+/// host's [`CId`] in place of a remote client's. For example, the host's player would use
+/// `SNetDir::ToFrom(CIdSpec::Except(host.cid), CIdSpec::Only(host.cid))`, just like the players
+/// of the remote clients.
+///
+/// Messages that would only be sent to the host are skipped, since the host already has the data.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct Host {
+    /// The [`CId`] of the host's local player.
+    pub cid: CId,
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Host { cid: HOST_CID }
+    }
+}
+
+impl Host {
+    /// Creates a new [`Host`] with the given [`CId`] for the local player.
+    pub fn new(cid: CId) -> Self {
+        Host { cid }
+    }
+}
+
+/// Returns true if `spec` only matches the host, so there is no one to send to.
+///
+/// Only [`CIdSpec::Only`] with the host's [`CId`] is skipped. Other specs are sent as they are,
+/// since they may match remote clients, and the host's [`CId`] is not a connection, so the host
+/// is never sent to.
+pub(crate) fn is_self_send(host: Option<&Host>, spec: CIdSpec) -> bool {
+    match (host, spec) {
+        (Some(host), CIdSpec::Only(cid)) => cid == host.cid,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_send() {
+        let host = Host::default();
+        assert!(is_self_send(Some(&host), CIdSpec::Only(HOST_CID)));
+        assert!(!is_self_send(Some(&host), CIdSpec::Only(1)));
+        assert!(!is_self_send(Some(&host), CIdSpec::All));
+        assert!(!is_self_send(Some(&host), CIdSpec::Except(HOST_CID)));
+        assert!(!is_self_send(None, CIdSpec::Only(HOST_CID)));
+    }
+
+    #[test]
+    fn self_send_custom_cid() {
+        let host = Host::new(100);
+        assert!(is_self_send(Some(&host), CIdSpec::Only(100)));
+        assert!(!is_self_send(Some(&host), CIdSpec::Only(HOST_CID)));
+    }
+}
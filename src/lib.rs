@@ -16,9 +16,148 @@
 //! on the GitHub repo.
 
 #![warn(missing_debug_implementations, missing_copy_implementations)]
+#[cfg(feature = "animation")]
+pub mod animation;
 pub mod app;
+#[cfg(feature = "types")]
+pub mod asset;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bundle;
+pub mod client_entities;
+pub mod clock;
+pub mod config;
+pub mod connection;
+pub mod history;
+pub mod host;
+#[cfg(feature = "types")]
+pub mod material;
+#[cfg(feature = "rapier")]
+pub mod physics;
+pub mod protocol;
+#[cfg(feature = "reflect")]
+pub mod reflect;
+pub mod relay;
+#[cfg(feature = "replay")]
+pub mod replay;
+pub mod res;
+pub mod spectator;
+pub mod state;
 pub mod sync;
 #[cfg(feature = "types")]
 pub mod types;
+#[cfg(feature = "types")]
+pub mod world;
 
 pub use app::{AppExt, ClientPlugin, NetLabel, ServerPlugin, SyncC};
+#[cfg(feature = "types")]
+pub use asset::{NetAssetComp, NetAssetConv, NetAssetKeys};
+#[cfg(feature = "audio")]
+pub use audio::{NetAudioCue, NetAudioListener};
+pub use bundle::{NetBundle, NetBundleComps};
+pub use client_entities::{ClientEntities, KeepOnDisconnect};
+pub use clock::ServerTime;
+pub use config::{NetConfig, NetRunCriteria};
+pub use connection::{
+    ClientConnected, ClientDisconnected, ConnectedToServer, ConnectionFailed, ConnectionPolicy,
+    ConnectionRejected, DisconnectedFromServer,
+};
+pub use history::{rewind, CompHistory};
+pub use host::{Host, HOST_CID};
+#[cfg(feature = "types")]
+pub use material::NetMaterialComp;
+#[cfg(feature = "rapier")]
+pub use physics::{NetBody, NetBodyMode};
+pub use protocol::{NetProtocol, ProtocolMismatch, ProtocolTimeout, RegError};
+#[cfg(feature = "reflect")]
+pub use reflect::{NetReflect, ReflectReplicate};
+pub use relay::NetRelay;
+#[cfg(feature = "replay")]
+pub use replay::{NetRecorder, NetReplay};
+pub use spectator::{SpectatorPlugin, Spectators};
+pub use state::{ConnectTimedOut, ConnectTimeout, NetState, NetStatePlugin};
+
+#[cfg(feature = "derive")]
+pub use bevy_pigeon_derive::NetMessage;
+
+/// Cases that the [`NetMessage`] derive must reject.
+///
+/// Unknown attributes:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health {
+///     #[net(unknown)]
+///     current: f32,
+/// }
+/// ```
+///
+/// A step that isn't a float:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health {
+///     #[net(quantize = "0.1")]
+///     current: f32,
+/// }
+/// ```
+///
+/// More than one of `skip`, `quantize` and `with` on a field:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health {
+///     #[net(skip, quantize = 0.1)]
+///     current: f32,
+/// }
+/// ```
+///
+/// A type for `with` that isn't a string:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health {
+///     #[net(with = 5)]
+///     current: f32,
+/// }
+/// ```
+///
+/// A name that isn't a string:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// #[net(name = NetHealth)]
+/// struct Health {
+///     current: f32,
+/// }
+/// ```
+///
+/// Enums and generic types:
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// enum Health {
+///     Alive(f32),
+///     Dead,
+/// }
+/// ```
+/// ```compile_fail
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health<T> {
+///     current: T,
+/// }
+/// ```
+///
+/// While this compiles:
+/// ```
+/// #[derive(Clone, bevy_pigeon::NetMessage)]
+/// struct Health {
+///     #[net(quantize = 0.1f32)]
+///     current: f32,
+///     #[net(skip)]
+///     regen: u32,
+/// }
+/// ```
+#[cfg(all(doctest, feature = "derive"))]
+pub struct NetMessageCompileFail;
+
+/// Re-exports used by the code generated by the derive macros. Not public API.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+}
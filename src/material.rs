@@ -0,0 +1,174 @@
+//! Syncing material parameters.
+//!
+//! [`StandardMaterial`]s are assets, so they can't be synced with a
+//! [`NetComp`](crate::sync::NetComp). Instead, entities with a [`NetMaterialComp`] send the
+//! parameters of the material their `Handle<StandardMaterial>` points to as a
+//! [`NetMaterial`](crate::types::NetMaterial), and the receiver applies them to the material its
+//! own handle points to.
+//!
+//! Since the received values are written to the material asset, every entity that shares the
+//! material changes with it. Give each synced entity its own material.
+
+use crate::app::{get_latest_msg, send_msg, send_target, Recorder};
+use crate::host::Host;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetCompMsg, NetEntity, SNetDir};
+use crate::types::NetMaterial;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use carrier_pigeon::net::{CIdSpec, NetMsg};
+use carrier_pigeon::{Client, Server};
+
+/// A component that tells `bevy-pigeon` to sync the parameters of the entity's
+/// [`StandardMaterial`].
+///
+/// This is the same as [`NetComp`](crate::sync::NetComp), but for
+/// [`sync_materials`](crate::AppExt::sync_materials).
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NetMaterialComp {
+    /// Change detection.
+    ///
+    /// If enabled, this only sends a message if the material was modified, or the handle changed.
+    pub cd: bool,
+    /// The timestamp of the last message received and written to the material.
+    pub last: Option<u32>,
+    /// The net direction for the client.
+    pub c_dir: CNetDir,
+    /// The net direction for the server.
+    pub s_dir: SNetDir,
+}
+
+impl Default for NetMaterialComp {
+    fn default() -> Self {
+        NetMaterialComp {
+            cd: true,
+            last: None,
+            c_dir: CNetDir::From,
+            s_dir: SNetDir::To(CIdSpec::All),
+        }
+    }
+}
+
+impl NetMaterialComp {
+    /// Creates a new [`NetMaterialComp`] with the given net directions.
+    pub fn new(cd: bool, c_dir: CNetDir, s_dir: SNetDir) -> Self {
+        NetMaterialComp {
+            cd,
+            last: None,
+            c_dir,
+            s_dir,
+        }
+    }
+}
+
+/// A system that sends the parameters of the materials of the entities with a
+/// [`NetMaterialComp`].
+///
+/// This skips the [`AssetEvent`]s while not connected, so it should run without the connected
+/// run criteria. Most of the time, you will call
+/// [`sync_materials`](crate::AppExt::sync_materials) which will add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn material_send(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    host: Option<Res<Host>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut events: EventReader<AssetEvent<StandardMaterial>>,
+    q: Query<(
+        &NetEntity,
+        &NetMaterialComp,
+        &Handle<StandardMaterial>,
+        ChangeTrackers<Handle<StandardMaterial>>,
+    )>,
+) {
+    let server = server.as_deref();
+    let client = client.as_deref();
+    if server.is_none() && client.is_none() {
+        // Skip the events while not connected, so old ones aren't sent when connecting.
+        events.clear();
+        return;
+    }
+    let modified: HashSet<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => Some(handle.id()),
+            AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+
+    for (net_e, net_c, handle, ct) in q.iter() {
+        let changed = ct.is_changed() || modified.contains(&handle.id());
+        let to_spec = send_target(
+            server.is_some(),
+            host.as_deref(),
+            net_c.cd,
+            changed,
+            net_c.c_dir,
+            net_c.s_dir,
+        );
+        if let (Some(to_spec), Some(material)) = (to_spec, materials.get(handle)) {
+            let msg = NetCompMsg::new(net_e.id, NetMaterial::from(material));
+            send_msg(
+                server,
+                client,
+                spectators.as_deref(),
+                recorder.as_deref(),
+                to_spec,
+                &msg,
+            );
+        }
+    }
+}
+
+/// A system that receives material parameters and applies them to the materials of the entities
+/// with a [`NetMaterialComp`].
+///
+/// Most of the time, you will call [`sync_materials`](crate::AppExt::sync_materials) which will
+/// add this system.
+pub fn material_recv(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    spectators: Option<Res<Spectators>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut q: Query<(&NetEntity, &mut NetMaterialComp, &Handle<StandardMaterial>)>,
+) {
+    let (msgs, is_server): (Vec<NetMsg<NetCompMsg<NetMaterial>>>, bool) =
+        if let Some(server) = &server {
+            // Ignore the messages from spectators.
+            let msgs = server
+                .recv::<NetCompMsg<NetMaterial>>()
+                .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+                .collect();
+            (msgs, true)
+        } else if let Some(client) = &client {
+            (client.recv::<NetCompMsg<NetMaterial>>().collect(), false)
+        } else {
+            return;
+        };
+
+    for (net_e, mut net_c, handle) in q.iter_mut() {
+        let spec = if is_server {
+            match net_c.s_dir.from() {
+                Some(spec) => *spec,
+                None => continue,
+            }
+        } else if net_c.c_dir == CNetDir::From {
+            CIdSpec::All
+        } else {
+            continue;
+        };
+
+        if let Some(valid_msg) = get_latest_msg(&msgs, net_c.last, spec, net_e.id) {
+            net_c.last = valid_msg.time;
+            match materials.get_mut(handle) {
+                Some(material) => valid_msg.msg.apply(material),
+                None => warn!(
+                    "Received material parameters for NetEntity {{ id: {} }}, but its material isn't loaded.",
+                    net_e.id
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Syncing physics bodies from `bevy_rapier3d`.
+//!
+//! Syncing a rigid-body's [`Transform`] alone causes jitter, since the velocity isn't sent and the
+//! local physics engine fights the received state. With [`sync_physics`](crate::AppExt::sync_physics),
+//! the [`Velocity`] is synced too, and the bodies with a [`NetBody`] are taken out of the local
+//! simulation on the peers that receive them.
+//!
+//! Whether a peer is authoritative over a body is decided by the net directions of the
+//! [`NetComp<Velocity, NetVelocity>`](NetComp): the peer that sends the velocity simulates the body,
+//! and the peers that receive it don't.
+
+use crate::sync::{CNetDir, NetComp};
+use crate::types::NetVelocity;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{RigidBody, Sleeping, Velocity};
+use carrier_pigeon::{Client, Server};
+
+/// How a [`NetBody`] is kept out of the local simulation on the peers that receive it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum NetBodyMode {
+    /// The body is made [`RigidBody::KinematicVelocityBased`], so it keeps moving with the
+    /// received velocity between updates, and isn't pushed around by other bodies.
+    Kinematic,
+    /// The body is kept asleep, so the received state is not simulated until it is woken up.
+    ///
+    /// A [`Sleeping`] component is inserted if the body doesn't have one.
+    Sleeping,
+}
+
+/// A component that marks a rigid-body whose state is synced.
+///
+/// Any entity using [`NetBody`] needs a [`NetComp<Velocity, NetVelocity>`](NetComp), and usually a
+/// `NetComp<Transform, NetTransform>` too.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NetBody {
+    /// How the body is kept out of the simulation on the peers that receive it.
+    pub mode: NetBodyMode,
+    /// The [`RigidBody`] that is used on the authoritative peer.
+    pub body: RigidBody,
+}
+
+impl Default for NetBody {
+    fn default() -> Self {
+        NetBody {
+            mode: NetBodyMode::Kinematic,
+            body: RigidBody::Dynamic,
+        }
+    }
+}
+
+impl NetBody {
+    /// Creates a new [`NetBody`] with `mode`, that is [`RigidBody::Dynamic`] on the authoritative
+    /// peer.
+    pub fn new(mode: NetBodyMode) -> Self {
+        NetBody {
+            mode,
+            body: RigidBody::Dynamic,
+        }
+    }
+}
+
+/// A system that sets up the rigid-bodies with a [`NetBody`], depending on whether this peer is
+/// authoritative over them.
+///
+/// Most of the time, you will call [`sync_physics`](crate::AppExt::sync_physics) which will add
+/// this system.
+#[allow(clippy::type_complexity)]
+pub fn net_body_mode(
+    mut commands: Commands,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut q: Query<(
+        Entity,
+        &NetBody,
+        &NetComp<Velocity, NetVelocity>,
+        &mut RigidBody,
+        Option<&mut Sleeping>,
+    )>,
+) {
+    let is_server = server.is_some();
+    if !is_server && client.is_none() {
+        return;
+    }
+
+    for (entity, net_b, net_c, body, sleeping) in q.iter_mut() {
+        let authoritative = if is_server {
+            net_c.s_dir.from().is_none()
+        } else {
+            net_c.c_dir == CNetDir::To
+        };
+        set_body_mode(&mut commands, entity, net_b, authoritative, body, sleeping);
+    }
+}
+
+/// Sets up the rigid-body of `entity`, depending on whether this peer is `authoritative` over it.
+fn set_body_mode(
+    commands: &mut Commands,
+    entity: Entity,
+    net_b: &NetBody,
+    authoritative: bool,
+    mut body: Mut<RigidBody>,
+    sleeping: Option<Mut<Sleeping>>,
+) {
+    let wanted = match (authoritative, net_b.mode) {
+        (true, _) | (false, NetBodyMode::Sleeping) => net_b.body,
+        (false, NetBodyMode::Kinematic) => RigidBody::KinematicVelocityBased,
+    };
+    if *body != wanted {
+        *body = wanted;
+    }
+
+    if let (false, NetBodyMode::Sleeping) = (authoritative, net_b.mode) {
+        match sleeping {
+            Some(mut sleeping) => {
+                if !sleeping.sleeping {
+                    sleeping.sleeping = true;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(Sleeping {
+                    sleeping: true,
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource)]
+    struct Authoritative(bool);
+
+    fn set_modes(
+        mut commands: Commands,
+        authoritative: Res<Authoritative>,
+        mut q: Query<(Entity, &NetBody, &mut RigidBody, Option<&mut Sleeping>)>,
+    ) {
+        for (entity, net_b, body, sleeping) in q.iter_mut() {
+            set_body_mode(
+                &mut commands,
+                entity,
+                net_b,
+                authoritative.0,
+                body,
+                sleeping,
+            );
+        }
+    }
+
+    fn app(authoritative: bool) -> App {
+        let mut app = App::new();
+        app.insert_resource(Authoritative(authoritative))
+            .add_system(set_modes);
+        app
+    }
+
+    #[test]
+    fn kinematic() {
+        let mut app = app(false);
+        let entity = app
+            .world
+            .spawn((NetBody::new(NetBodyMode::Kinematic), RigidBody::Dynamic))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world.get::<RigidBody>(entity),
+            Some(&RigidBody::KinematicVelocityBased)
+        );
+        assert!(app.world.get::<Sleeping>(entity).is_none());
+
+        // Becoming authoritative restores the body.
+        app.insert_resource(Authoritative(true));
+        app.update();
+        assert_eq!(
+            app.world.get::<RigidBody>(entity),
+            Some(&RigidBody::Dynamic)
+        );
+    }
+
+    #[test]
+    fn sleeping_inserted() {
+        let mut app = app(false);
+        let entity = app
+            .world
+            .spawn((NetBody::new(NetBodyMode::Sleeping), RigidBody::Dynamic))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world.get::<RigidBody>(entity),
+            Some(&RigidBody::Dynamic)
+        );
+        assert!(app
+            .world
+            .get::<Sleeping>(entity)
+            .map_or(false, |s| s.sleeping));
+    }
+
+    #[test]
+    fn sleeping_kept_asleep() {
+        let mut app = app(false);
+        let awake = Sleeping {
+            sleeping: false,
+            ..default()
+        };
+        let entity = app
+            .world
+            .spawn((
+                NetBody::new(NetBodyMode::Sleeping),
+                RigidBody::Dynamic,
+                awake,
+            ))
+            .id();
+        app.update();
+        assert!(app
+            .world
+            .get::<Sleeping>(entity)
+            .map_or(false, |s| s.sleeping));
+    }
+
+    #[test]
+    fn authoritative() {
+        let mut app = app(true);
+        let entity = app
+            .world
+            .spawn((
+                NetBody::new(NetBodyMode::Sleeping),
+                RigidBody::KinematicVelocityBased,
+            ))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world.get::<RigidBody>(entity),
+            Some(&RigidBody::Dynamic)
+        );
+        assert!(app.world.get::<Sleeping>(entity).is_none());
+    }
+}
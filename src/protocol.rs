@@ -0,0 +1,454 @@
+//! Protocol checking.
+//!
+//! Every message that `bevy-pigeon` registers into a message table is recorded in the
+//! [`NetProtocol`] resource. A hash of these, along with the version of `bevy-pigeon`, is
+//! exchanged when a client connects, so that peers that would mis-decode each other's messages
+//! are rejected instead.
+//!
+//! It is also used to detect messages that are registered into a `SortedMsgTable` with the same
+//! id.
+
+use crate::app::{record_sent, Recorder};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use carrier_pigeon::{CId, Client, MsgRegError, Server, Transport};
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, Any};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+/// The messages registered by `bevy-pigeon`, used to compute the protocol hash and to detect
+/// duplicate message ids.
+///
+/// This is inserted and filled in by the [`AppExt`](crate::AppExt) methods.
+#[derive(Resource, Clone, Eq, PartialEq, Debug)]
+pub struct NetProtocol {
+    /// The messages registered into a `MsgTable`, in the order they were registered.
+    ordered: Vec<(String, Transport)>,
+    /// The messages registered into a `SortedMsgTable`, along with the name of their type.
+    sorted: Vec<(String, Transport, &'static str)>,
+    /// The protocol hash, updated whenever a message is recorded.
+    hash: u64,
+}
+
+impl Default for NetProtocol {
+    fn default() -> Self {
+        let mut protocol = NetProtocol {
+            ordered: vec![],
+            sorted: vec![],
+            hash: 0,
+        };
+        protocol.hash = protocol.compute_hash();
+        protocol
+    }
+}
+
+impl NetProtocol {
+    /// Records a message of type `T` registered into a `MsgTable`.
+    pub fn record<T: Any>(&mut self, transport: Transport) {
+        self.ordered.push((type_name::<T>().to_owned(), transport));
+        self.hash = self.compute_hash();
+    }
+
+    /// Records a message of type `T` registered into a `SortedMsgTable` with `id`.
+    pub fn record_sorted<T: Any>(&mut self, id: impl Into<String>, transport: Transport) {
+        self.sorted.push((id.into(), transport, type_name::<T>()));
+        self.hash = self.compute_hash();
+    }
+
+    /// Whether any message registered into a `MsgTable` was recorded.
+    pub(crate) fn has_ordered(&self) -> bool {
+        !self.ordered.is_empty()
+    }
+
+    /// Checks that `id` is not already used by a message registered into a `SortedMsgTable`.
+    pub fn check_sorted<T: Any>(&self, id: &str) -> Result<(), RegError> {
+        match self.sorted.iter().find(|(other, _, _)| other == id) {
+            Some((_, _, existing)) => Err(RegError::DuplicateId {
+                id: id.to_owned(),
+                existing: *existing,
+                new: type_name::<T>(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Gets the protocol hash.
+    ///
+    /// This is a 64 bit FNV-1a hash over the version of `bevy-pigeon`, and the id and transport
+    /// of every recorded message. Messages from a `MsgTable` are hashed in the order they were
+    /// registered, since that decides their ids, while messages from a `SortedMsgTable` are
+    /// hashed in sorted order.
+    ///
+    /// The hash is computed when messages are recorded, so this is cheap to call every frame.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Computes the protocol hash described in [`hash`](Self::hash).
+    fn compute_hash(&self) -> u64 {
+        let mut sorted: Vec<(&String, &Transport)> =
+            self.sorted.iter().map(|(id, t, _)| (id, t)).collect();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut hasher = Fnv1a::new();
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        let ordered = self.ordered.iter().map(|(id, t)| (id, t));
+        for (id, transport) in ordered.chain(sorted) {
+            hasher.write(&[0]);
+            hasher.write(id.as_bytes());
+            hasher.write(&[match transport {
+                Transport::TCP => 1,
+                Transport::UDP => 2,
+            }]);
+        }
+        hasher.finish()
+    }
+}
+
+/// An error from registering a message with an explicit id.
+#[derive(Debug)]
+pub enum RegError {
+    /// The id is already used by another message.
+    DuplicateId {
+        /// The id.
+        id: String,
+        /// The name of the type that already uses the id.
+        existing: &'static str,
+        /// The name of the type that was being registered.
+        new: &'static str,
+    },
+    /// The message table returned an error.
+    Table(MsgRegError),
+}
+
+impl Display for RegError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RegError::DuplicateId { id, existing, new } => write!(
+                f,
+                "The message id \"{}\" of `{}` is already used by `{}`",
+                id, new, existing
+            ),
+            RegError::Table(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RegError {}
+
+impl From<MsgRegError> for RegError {
+    fn from(e: MsgRegError) -> Self {
+        RegError::Table(e)
+    }
+}
+
+/// A 64 bit FNV-1a hasher.
+///
+/// This is used instead of [`std::collections::hash_map::DefaultHasher`], since its output is
+/// not guaranteed to be the same across Rust versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// An event that is fired when a peer has a different protocol hash.
+///
+/// On the server, the client is disconnected with the disconnect message given to
+/// [`check_protocol`](crate::AppExt::check_protocol).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ProtocolMismatch {
+    /// The [`CId`] of the client with the different protocol.
+    ///
+    /// This is `None` on the client, where the peer is the server.
+    pub cid: Option<CId>,
+    /// Our protocol hash.
+    pub local: u64,
+    /// The peer's protocol hash.
+    pub remote: u64,
+}
+
+/// The message that carries a protocol hash.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub(crate) struct ProtocolCheck {
+    pub(crate) hash: u64,
+}
+
+/// The disconnect message used to reject clients with a different protocol.
+///
+/// This is inserted by [`check_protocol`](crate::AppExt::check_protocol).
+#[derive(Resource, Clone, Debug)]
+pub struct ProtocolDiscon<D>(pub D);
+
+/// The amount of time the server waits for a new client's protocol check before disconnecting it.
+///
+/// This catches clients that never send one, like builds without
+/// [`check_protocol`](crate::AppExt::check_protocol). Defaults to 10 seconds.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct ProtocolTimeout(pub Duration);
+
+impl Default for ProtocolTimeout {
+    fn default() -> Self {
+        ProtocolTimeout(Duration::from_secs(10))
+    }
+}
+
+/// Sends our protocol hash to the server once the client is created, and checks the hash that
+/// the server replies with.
+///
+/// Most of the time, you will call [`check_protocol`](crate::AppExt::check_protocol) which will
+/// add this system.
+pub fn client_check_protocol(
+    client: Option<Res<Client>>,
+    recorder: Option<Res<Recorder>>,
+    protocol: Res<NetProtocol>,
+    mut ew: EventWriter<ProtocolMismatch>,
+) {
+    if let Some(client) = client {
+        let local = protocol.hash();
+        if client.is_added() {
+            let check = ProtocolCheck { hash: local };
+            if let Err(e) = client.send(&check) {
+                error!("{}", e);
+            }
+            record_sent(recorder.as_deref(), Vec::new, &check);
+        }
+
+        for msg in client.recv::<ProtocolCheck>() {
+            if msg.hash != local {
+                error!(
+                    "The server has a different protocol ({:x}) than us ({:x})",
+                    msg.hash, local
+                );
+                ew.send(ProtocolMismatch {
+                    cid: None,
+                    local,
+                    remote: msg.hash,
+                });
+            }
+        }
+    }
+}
+
+/// Checks the protocol hashes sent by new clients, replying with our own, and disconnects the
+/// clients that have a different protocol, or that don't send one within the
+/// [`ProtocolTimeout`].
+///
+/// Most of the time, you will call [`check_protocol`](crate::AppExt::check_protocol) which will
+/// add this system.
+#[allow(clippy::too_many_arguments)]
+pub fn server_check_protocol<D: Clone + Send + Sync + 'static>(
+    server: Option<ResMut<Server>>,
+    recorder: Option<Res<Recorder>>,
+    protocol: Res<NetProtocol>,
+    discon: Res<ProtocolDiscon<D>>,
+    timeout: Res<ProtocolTimeout>,
+    time: Res<Time>,
+    mut waiting: Local<HashMap<CId, Duration>>,
+    mut checked: Local<HashSet<CId>>,
+    mut ew: EventWriter<ProtocolMismatch>,
+) {
+    let mut server = match server {
+        Some(server) => server,
+        None => {
+            waiting.clear();
+            checked.clear();
+            return;
+        }
+    };
+
+    // Keep track of when each client connected, and forget the ones that left.
+    let now = time.elapsed();
+    let cids: HashSet<CId> = server.cids().collect();
+    waiting.retain(|cid, _| cids.contains(cid));
+    checked.retain(|cid| cids.contains(cid));
+    for cid in &cids {
+        if !checked.contains(cid) {
+            waiting.entry(*cid).or_insert(now);
+        }
+    }
+
+    let local = protocol.hash();
+    let checks: Vec<(CId, u64)> = server
+        .recv::<ProtocolCheck>()
+        .map(|msg| (msg.cid, msg.hash))
+        .collect();
+
+    for (cid, remote) in checks {
+        waiting.remove(&cid);
+        checked.insert(cid);
+        let check = ProtocolCheck { hash: local };
+        if let Err(e) = server.send_to(cid, &check) {
+            error!("{}", e);
+        }
+        record_sent(recorder.as_deref(), || vec![cid], &check);
+        if remote == local {
+            continue;
+        }
+
+        warn!(
+            "Client {} has a different protocol ({:x}) than us ({:x}). Disconnecting.",
+            cid, remote, local
+        );
+        ew.send(ProtocolMismatch {
+            cid: Some(cid),
+            local,
+            remote,
+        });
+        if let Err(e) = server.disconnect(&discon.0, cid) {
+            error!("{}", e);
+        }
+    }
+
+    let late: Vec<CId> = waiting
+        .iter()
+        .filter(|(_, since)| now - **since > timeout.0)
+        .map(|(cid, _)| *cid)
+        .collect();
+    for cid in late {
+        warn!(
+            "Client {} didn't send a protocol check within {:?}. Disconnecting.",
+            cid, timeout.0
+        );
+        waiting.remove(&cid);
+        if let Err(e) = server.disconnect(&discon.0, cid) {
+            error!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppExt;
+    use carrier_pigeon::MsgTable;
+
+    #[derive(Component, Serialize, Deserialize, Clone, Debug)]
+    struct A(u32);
+
+    #[derive(Component, Serialize, Deserialize, Clone, Debug)]
+    struct B(u32);
+
+    /// Builds the protocol of a peer that syncs `A`, and `B` too if `extra` is set.
+    fn protocol(extra: bool) -> NetProtocol {
+        let mut app = App::new();
+        let mut table = MsgTable::new();
+        app.check_protocol(&mut table, ())
+            .sync_comp::<A, A>(&mut table, Transport::TCP);
+        if extra {
+            app.sync_comp::<B, B>(&mut table, Transport::TCP);
+        }
+        app.world.resource::<NetProtocol>().clone()
+    }
+
+    #[test]
+    fn check_registered_first() {
+        let one = protocol(false);
+        let two = protocol(true);
+
+        // The check message has the first id in both tables, so it is decoded correctly...
+        assert_eq!(one.ordered[0].0, type_name::<ProtocolCheck>());
+        assert_eq!(two.ordered[0].0, type_name::<ProtocolCheck>());
+        // ...and it carries different hashes.
+        assert_ne!(one.hash(), two.hash());
+    }
+
+    #[test]
+    #[should_panic]
+    fn check_registered_late() {
+        let mut app = App::new();
+        let mut table = MsgTable::new();
+        app.sync_comp::<A, A>(&mut table, Transport::TCP)
+            .check_protocol(&mut table, ());
+    }
+
+    #[test]
+    fn hash_stable() {
+        let mut one = NetProtocol::default();
+        one.record::<A>(Transport::TCP);
+        one.record_sorted::<B>("b", Transport::UDP);
+        let two = one.clone();
+        assert_eq!(one.hash(), two.hash());
+        assert_eq!(protocol(true).hash(), protocol(true).hash());
+    }
+
+    #[test]
+    fn hash_updated_on_record() {
+        let mut protocol = NetProtocol::default();
+        assert_eq!(protocol.hash(), protocol.compute_hash());
+        let empty = protocol.hash();
+
+        protocol.record::<A>(Transport::TCP);
+        assert_ne!(protocol.hash(), empty);
+        assert_eq!(protocol.hash(), protocol.compute_hash());
+        protocol.record_sorted::<B>("b", Transport::UDP);
+        assert_eq!(protocol.hash(), protocol.compute_hash());
+    }
+
+    #[test]
+    fn hash_ordered_order() {
+        let mut one = NetProtocol::default();
+        one.record::<A>(Transport::TCP);
+        one.record::<B>(Transport::TCP);
+        let mut two = NetProtocol::default();
+        two.record::<B>(Transport::TCP);
+        two.record::<A>(Transport::TCP);
+        assert_ne!(one.hash(), two.hash());
+    }
+
+    #[test]
+    fn hash_sorted_order() {
+        let mut one = NetProtocol::default();
+        one.record_sorted::<A>("a", Transport::TCP);
+        one.record_sorted::<B>("b", Transport::TCP);
+        let mut two = NetProtocol::default();
+        two.record_sorted::<B>("b", Transport::TCP);
+        two.record_sorted::<A>("a", Transport::TCP);
+        assert_eq!(one.hash(), two.hash());
+    }
+
+    #[test]
+    fn hash_transport() {
+        let mut one = NetProtocol::default();
+        one.record::<A>(Transport::TCP);
+        let mut two = NetProtocol::default();
+        two.record::<A>(Transport::UDP);
+        assert_ne!(one.hash(), two.hash());
+
+        let mut one = NetProtocol::default();
+        one.record_sorted::<A>("a", Transport::TCP);
+        let mut two = NetProtocol::default();
+        two.record_sorted::<A>("a", Transport::UDP);
+        assert_ne!(one.hash(), two.hash());
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let mut protocol = NetProtocol::default();
+        protocol.record_sorted::<A>("id", Transport::TCP);
+        assert!(protocol.check_sorted::<B>("other").is_ok());
+        match protocol.check_sorted::<B>("id") {
+            Err(RegError::DuplicateId { id, existing, new }) => {
+                assert_eq!(id, "id");
+                assert_eq!(existing, type_name::<A>());
+                assert_eq!(new, type_name::<B>());
+            }
+            other => panic!("expected a `DuplicateId`, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! Replication based on bevy's reflection.
+//!
+//! Instead of registering every synced type with [`sync_comp`](crate::AppExt::sync_comp), the
+//! components of an entity with a [`NetReflect`] are found through the [`AppTypeRegistry`]. Any
+//! registered component with the [`ReflectReplicate`] type data is serialized with bevy's
+//! [`ReflectSerializer`] and sent. This allows components that are not known at compile time, such
+//! as ones defined by mods, scripts or an editor, to be networked.
+//!
+//! ```ignore
+//! #[derive(Component, Reflect, Default)]
+//! #[reflect(Component, Replicate)]
+//! struct Health(f32);
+//!
+//! app.register_type::<Health>()
+//!     .sync_reflect(&mut table, Transport::UDP);
+//! ```
+
+use crate::app::{send_msg, send_target, Recorder};
+use crate::host::Host;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetEntity, SNetDir};
+use bevy::prelude::*;
+use bevy::reflect::serde::{ReflectSerializer, UntypedReflectDeserializer};
+use bevy::reflect::FromType;
+use bevy::utils::HashMap;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{CId, Client, Server};
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::fmt::{self, Debug, Formatter};
+
+/// The type data that marks a component to be replicated by [`NetReflect`].
+///
+/// Add it to a component with `#[reflect(Replicate)]`.
+#[derive(Copy, Clone)]
+pub struct ReflectReplicate {
+    reflect: for<'a> fn(&'a World, Entity) -> Option<&'a dyn Reflect>,
+    apply: fn(&mut World, Entity, &dyn Reflect),
+    is_changed: fn(&World, Entity, u32, u32) -> bool,
+}
+
+impl ReflectReplicate {
+    /// Gets the component of `entity` as a `&dyn Reflect`.
+    pub fn reflect<'a>(&self, world: &'a World, entity: Entity) -> Option<&'a dyn Reflect> {
+        (self.reflect)(world, entity)
+    }
+
+    /// Applies `value` to the component of `entity`.
+    ///
+    /// Does nothing if `entity` doesn't have the component.
+    pub fn apply(&self, world: &mut World, entity: Entity, value: &dyn Reflect) {
+        (self.apply)(world, entity, value)
+    }
+
+    /// Returns true if the component of `entity` changed since `last_change_tick`, by bevy's
+    /// change detection.
+    ///
+    /// Returns false if `entity` doesn't have the component.
+    pub fn is_changed(
+        &self,
+        world: &World,
+        entity: Entity,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> bool {
+        (self.is_changed)(world, entity, last_change_tick, change_tick)
+    }
+}
+
+impl Debug for ReflectReplicate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReflectReplicate").finish()
+    }
+}
+
+impl<C: Component + Reflect> FromType<C> for ReflectReplicate {
+    fn from_type() -> Self {
+        ReflectReplicate {
+            reflect: reflect_comp::<C>,
+            apply: apply_comp::<C>,
+            is_changed: is_comp_changed::<C>,
+        }
+    }
+}
+
+fn reflect_comp<C: Component + Reflect>(world: &World, entity: Entity) -> Option<&dyn Reflect> {
+    world.get::<C>(entity).map(|c| c as &dyn Reflect)
+}
+
+fn apply_comp<C: Component + Reflect>(world: &mut World, entity: Entity, value: &dyn Reflect) {
+    if let Some(mut c) = world.get_mut::<C>(entity) {
+        c.apply(value);
+    }
+}
+
+fn is_comp_changed<C: Component + Reflect>(
+    world: &World,
+    entity: Entity,
+    last_change_tick: u32,
+    change_tick: u32,
+) -> bool {
+    world
+        .get_entity(entity)
+        .and_then(|e| e.get_change_ticks::<C>())
+        .map_or(false, |ticks| {
+            ticks.is_changed(last_change_tick, change_tick)
+        })
+}
+
+/// A component that tells `bevy-pigeon` to sync all the components of this entity that have the
+/// [`ReflectReplicate`] type data.
+///
+/// Any entity using [`NetReflect`] needs a [`NetEntity`]. The components must already exist on
+/// the receiving end; they are updated, not inserted.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NetReflect {
+    /// Change detection.
+    ///
+    /// If enabled, a component is only sent if its serialized value changed since it was last
+    /// sent.
+    pub cd: bool,
+    /// The net direction for the client.
+    pub c_dir: CNetDir,
+    /// The net direction for the server.
+    pub s_dir: SNetDir,
+}
+
+impl Default for NetReflect {
+    fn default() -> Self {
+        NetReflect {
+            cd: true,
+            c_dir: CNetDir::From,
+            s_dir: SNetDir::To(CIdSpec::All),
+        }
+    }
+}
+
+impl NetReflect {
+    /// Creates a new [`NetReflect`] with the given net directions.
+    /// Change detection (cd) defaults to true.
+    pub fn new(cd: bool, c_dir: CNetDir, s_dir: SNetDir) -> Self {
+        NetReflect { cd, c_dir, s_dir }
+    }
+}
+
+/// The message that carries a reflected component.
+///
+/// `data` is the component serialized with the [`ReflectSerializer`] in `ron`, which includes
+/// the type name.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct NetReflectMsg {
+    pub(crate) id: u64,
+    pub(crate) data: String,
+}
+
+/// A system that sends the replicated components of entities with a [`NetReflect`].
+///
+/// Most of the time, you will call [`sync_reflect`](crate::AppExt::sync_reflect) which will add
+/// this system. Only add it manually if you know what you are doing and want custom control over
+/// when it runs.
+pub fn reflect_send(world: &mut World, mut sent: Local<HashMap<(Entity, TypeId), String>>) {
+    let is_server = world.contains_resource::<Server>();
+    if !is_server && !world.contains_resource::<Client>() {
+        return;
+    }
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let host = world.get_resource::<Host>().copied();
+    let last_change_tick = world.last_change_tick();
+    let change_tick = world.read_change_tick();
+
+    // Forget the components of despawned entities.
+    sent.retain(|(entity, _), _| world.get_entity(*entity).is_some());
+
+    let replicated: Vec<(TypeId, ReflectReplicate)> = registry
+        .iter()
+        .filter_map(|registration| {
+            let replicate = registration.data::<ReflectReplicate>()?;
+            Some((registration.type_id(), *replicate))
+        })
+        .collect();
+    if replicated.is_empty() {
+        return;
+    }
+
+    let mut msgs = vec![];
+    let mut q = world.query::<(Entity, &NetEntity, &NetReflect)>();
+    for (entity, net_e, net_r) in q.iter(world) {
+        for (type_id, replicate) in replicated.iter() {
+            let changed = replicate.is_changed(world, entity, last_change_tick, change_tick);
+            let to_spec = match send_target(
+                is_server,
+                host.as_ref(),
+                net_r.cd,
+                changed,
+                net_r.c_dir,
+                net_r.s_dir,
+            ) {
+                Some(to_spec) => to_spec,
+                None => continue,
+            };
+            let value = match replicate.reflect(world, entity) {
+                Some(value) => value,
+                None => continue,
+            };
+            let data = match ron::to_string(&ReflectSerializer::new(value, &registry)) {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("{}", e);
+                    continue;
+                }
+            };
+
+            // Bevy's change detection has false positives, like components written by
+            // `reflect_recv`, so skip the ones whose value is the same as was last sent.
+            let key = (entity, *type_id);
+            if net_r.cd && sent.get(&key) == Some(&data) {
+                continue;
+            }
+            sent.insert(key, data.clone());
+            msgs.push((to_spec, NetReflectMsg { id: net_e.id, data }));
+        }
+    }
+
+    let server = world.get_resource::<Server>();
+    let client = world.get_resource::<Client>();
+    let spectators = world.get_resource::<Spectators>();
+    let recorder = world.get_resource::<Recorder>();
+    for (to_spec, msg) in msgs {
+        send_msg(server, client, spectators, recorder, to_spec, &msg);
+    }
+}
+
+/// A system that receives reflected components and applies them to entities with a
+/// [`NetReflect`].
+///
+/// Only the newest message for each component of an entity is applied, by the send time of the
+/// messages, so a late packet doesn't overwrite newer state.
+///
+/// Most of the time, you will call [`sync_reflect`](crate::AppExt::sync_reflect) which will add
+/// this system. Only add it manually if you know what you are doing and want custom control over
+/// when it runs.
+pub fn reflect_recv(world: &mut World, mut last: Local<HashMap<(Entity, String), u32>>) {
+    let msgs: Vec<(CId, Option<u32>, NetReflectMsg)> =
+        if let Some(server) = world.get_resource::<Server>() {
+            let spectators = world.get_resource::<Spectators>();
+            server
+                .recv::<NetReflectMsg>()
+                .filter(|msg| !is_spectator(spectators, msg.cid))
+                .map(|msg| (msg.cid, msg.time, (*msg).clone()))
+                .collect()
+        } else if let Some(client) = world.get_resource::<Client>() {
+            client
+                .recv::<NetReflectMsg>()
+                .map(|msg| (msg.cid, msg.time, (*msg).clone()))
+                .collect()
+        } else {
+            return;
+        };
+
+    let is_server = world.contains_resource::<Server>();
+    apply_reflect_msgs(world, &mut last, msgs, is_server);
+}
+
+/// Applies the newest of the reflected components in `msgs`, along with the [`CId`] each was
+/// received from, and the time it was sent.
+///
+/// `last` holds the send time of the last value applied to each component of each entity.
+pub(crate) fn apply_reflect_msgs(
+    world: &mut World,
+    last: &mut HashMap<(Entity, String), u32>,
+    msgs: Vec<(CId, Option<u32>, NetReflectMsg)>,
+    is_server: bool,
+) {
+    // Forget the components of despawned entities.
+    last.retain(|(entity, _), _| world.get_entity(*entity).is_some());
+    if msgs.is_empty() {
+        return;
+    }
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut q = world.query::<(Entity, &NetEntity, &NetReflect)>();
+    let entities: HashMap<u64, (Entity, NetReflect)> = q
+        .iter(world)
+        .map(|(entity, net_e, net_r)| (net_e.id, (entity, *net_r)))
+        .collect();
+
+    // The newest value for each component of each entity.
+    let mut latest: HashMap<(Entity, String), (Option<u32>, Box<dyn Reflect>)> = HashMap::new();
+    for (cid, time, msg) in msgs {
+        let (entity, net_r) = match entities.get(&msg.id) {
+            Some(item) => *item,
+            None => continue,
+        };
+        let valid = if is_server {
+            net_r.s_dir.from().map_or(false, |spec| spec.matches(cid))
+        } else {
+            net_r.c_dir == CNetDir::From
+        };
+        if !valid {
+            continue;
+        }
+
+        let value = match ron::Deserializer::from_str(&msg.data)
+            .map_err(ron::Error::from)
+            .and_then(|mut de| UntypedReflectDeserializer::new(&registry).deserialize(&mut de))
+        {
+            Ok(value) => value,
+            Err(e) => {
+                error!("{}", e);
+                continue;
+            }
+        };
+
+        let key = (entity, value.type_name().to_owned());
+        let current = latest
+            .get(&key)
+            .map_or_else(|| last.get(&key).copied(), |(time, _)| *time);
+        if is_newer(time, current) {
+            latest.insert(key, (time, value));
+        }
+    }
+
+    for ((entity, type_name), (time, value)) in latest {
+        match registry
+            .get_with_name(&type_name)
+            .and_then(|registration| registration.data::<ReflectReplicate>())
+        {
+            Some(replicate) => replicate.apply(world, entity, &*value),
+            None => {
+                warn!(
+                    "Received a reflected `{}`, which is not registered with `#[reflect(Replicate)]`.",
+                    type_name
+                );
+                continue;
+            }
+        }
+        if let Some(time) = time {
+            last.insert((entity, type_name), time);
+        }
+    }
+}
+
+/// Whether a message sent at `time` is newer than one sent at `current`.
+///
+/// This is the same rule as [`get_latest_msg`](crate::app::get_latest_msg): messages without a
+/// send time are always newer.
+fn is_newer(time: Option<u32>, current: Option<u32>) -> bool {
+    match time {
+        Some(time) => time > current.unwrap_or(0),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component, Replicate)]
+    struct Health(f32);
+
+    #[test]
+    fn round_trip() {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        registry.write().register::<Health>();
+        let registry = registry.read();
+        let replicate = *registry
+            .get(TypeId::of::<Health>())
+            .and_then(|registration| registration.data::<ReflectReplicate>())
+            .expect("`Health` has the `ReflectReplicate` type data");
+
+        let sender = world.spawn(Health(7.5)).id();
+        let receiver = world.spawn(Health(0.0)).id();
+
+        let value = replicate
+            .reflect(&world, sender)
+            .expect("the sender has `Health`");
+        let msg = NetReflectMsg {
+            id: 5,
+            data: ron::to_string(&ReflectSerializer::new(value, &registry)).unwrap(),
+        };
+        // carrier-pigeon sends messages with bincode, and the value inside is ron.
+        let data = bincode::serialize(&msg).unwrap();
+        let msg: NetReflectMsg = bincode::deserialize(&data).unwrap();
+        assert_eq!(msg.id, 5);
+        let mut de = ron::Deserializer::from_str(&msg.data).unwrap();
+        let value = UntypedReflectDeserializer::new(&registry)
+            .deserialize(&mut de)
+            .unwrap();
+        assert_eq!(value.type_name(), std::any::type_name::<Health>());
+
+        replicate.apply(&mut world, receiver, &*value);
+        assert_eq!(world.get::<Health>(receiver), Some(&Health(7.5)));
+    }
+
+    #[test]
+    fn newer() {
+        assert!(is_newer(Some(5), None));
+        assert!(is_newer(Some(5), Some(4)));
+        assert!(!is_newer(Some(4), Some(5)));
+        assert!(!is_newer(Some(5), Some(5)));
+        // Messages without a send time are always applied.
+        assert!(is_newer(None, Some(5)));
+    }
+}
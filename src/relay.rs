@@ -0,0 +1,200 @@
+//! Relaying component messages between clients.
+//!
+//! Normally, the server applies the components that a client sends to its own entity, and then
+//! sends them on to the other clients. When relaying, the server forwards the component messages
+//! from the client that owns an entity straight to the other clients, without needing the entity,
+//! or even the component type, in its own world. This makes pure relay servers possible.
+
+use crate::app::Recorder;
+use crate::connection::ClientDisconnected;
+use crate::spectator::{is_spectator, send_spec, Spectators};
+use crate::sync::NetCompMsg;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{CId, Server};
+use serde::Serialize;
+use std::any::Any;
+
+/// The owners of the relayed entities.
+///
+/// Only the component messages from the client that owns an entity are relayed, and they are
+/// sent to every other client. This is only used on the server.
+#[derive(Resource, Clone, Eq, PartialEq, Debug, Default)]
+pub struct NetRelay {
+    owners: HashMap<u64, CId>,
+    /// If enabled, the first client to send a component of an entity with no owner becomes its
+    /// owner.
+    ///
+    /// Otherwise, the messages of entities with no owner are dropped.
+    ///
+    /// Defaults to false.
+    pub claim_unowned: bool,
+}
+
+impl NetRelay {
+    /// Creates a new, empty [`NetRelay`].
+    pub fn new(claim_unowned: bool) -> Self {
+        NetRelay {
+            owners: HashMap::default(),
+            claim_unowned,
+        }
+    }
+
+    /// Makes the client `cid` the owner of the [`NetEntity`](crate::sync::NetEntity) with `id`.
+    pub fn set_owner(&mut self, id: u64, cid: CId) {
+        self.owners.insert(id, cid);
+    }
+
+    /// Removes the owner of the [`NetEntity`](crate::sync::NetEntity) with `id`.
+    pub fn remove(&mut self, id: u64) -> Option<CId> {
+        self.owners.remove(&id)
+    }
+
+    /// Gets the owner of the [`NetEntity`](crate::sync::NetEntity) with `id`.
+    pub fn owner(&self, id: u64) -> Option<CId> {
+        self.owners.get(&id).copied()
+    }
+
+    /// Gets the ids of the entities owned by the client `cid`.
+    pub fn owned_by(&self, cid: CId) -> impl Iterator<Item = u64> + '_ {
+        self.owners
+            .iter()
+            .filter(move |(_, owner)| **owner == cid)
+            .map(|(id, _)| *id)
+    }
+
+    /// Checks whether the component message for the entity with `id`, from the client `cid`,
+    /// should be relayed, claiming the entity if allowed.
+    fn accept(&mut self, id: u64, cid: CId) -> bool {
+        match self.owners.get(&id) {
+            Some(owner) => *owner == cid,
+            None if self.claim_unowned => {
+                self.owners.insert(id, cid);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A system that relays the component messages of type `M` from the owning client to the other
+/// clients.
+///
+/// Messages from spectators are ignored.
+///
+/// Most of the time, you will call [`relay_comp`](crate::AppExt::relay_comp) which will add this
+/// system. Add it manually if the server also syncs the component with
+/// [`sync_comp`](crate::AppExt::sync_comp), which already registers the message.
+pub fn relay_comp_msgs<M: Clone + Any + Send + Sync + Serialize>(
+    server: Option<Res<Server>>,
+    relay: Option<ResMut<NetRelay>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+) {
+    let (server, mut relay) = match (server, relay) {
+        (Some(server), Some(relay)) => (server, relay),
+        _ => return,
+    };
+    let spectators = spectators.as_deref();
+    for msg in server.recv::<NetCompMsg<M>>() {
+        if !should_relay(&mut relay, spectators, msg.id, msg.cid) {
+            continue;
+        }
+        send_spec(
+            &server,
+            spectators,
+            recorder.as_deref(),
+            CIdSpec::Except(msg.cid),
+            &*msg,
+        );
+    }
+}
+
+/// Checks whether the component message for the entity with `id`, from the client `cid`, should be
+/// relayed.
+///
+/// Spectators can't claim entities, so they are checked first.
+fn should_relay(relay: &mut NetRelay, spectators: Option<&Spectators>, id: u64, cid: CId) -> bool {
+    !is_spectator(spectators, cid) && relay.accept(id, cid)
+}
+
+/// Forgets the entities owned by clients that disconnected.
+///
+/// Most of the time, you will call [`relay_comp`](crate::AppExt::relay_comp) which will add this
+/// system.
+pub fn remove_relay_owners(
+    relay: Option<ResMut<NetRelay>>,
+    mut er: EventReader<ClientDisconnected>,
+) {
+    if let Some(mut relay) = relay {
+        for discon in er.iter() {
+            relay.owners.retain(|_, owner| *owner != discon.cid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use carrier_pigeon::net::Status;
+
+    #[test]
+    fn accept_owner() {
+        let mut relay = NetRelay::new(false);
+        relay.set_owner(5, 1);
+
+        assert!(relay.accept(5, 1));
+        assert!(!relay.accept(5, 2));
+        // Unowned entities are dropped.
+        assert!(!relay.accept(6, 1));
+        assert_eq!(relay.owner(6), None);
+    }
+
+    #[test]
+    fn claim_unowned() {
+        let mut relay = NetRelay::new(true);
+
+        assert!(relay.accept(5, 2));
+        assert_eq!(relay.owner(5), Some(2));
+        assert!(!relay.accept(5, 1));
+        assert!(relay.accept(5, 2));
+    }
+
+    #[test]
+    fn ignore_spectators() {
+        let mut relay = NetRelay::new(true);
+        relay.set_owner(5, 3);
+        let mut spectators = Spectators::default();
+        spectators.insert(3);
+        spectators.insert(4);
+
+        assert!(!should_relay(&mut relay, Some(&spectators), 5, 3));
+        // Spectators don't claim unowned entities.
+        assert!(!should_relay(&mut relay, Some(&spectators), 6, 4));
+        assert_eq!(relay.owner(6), None);
+        assert!(should_relay(&mut relay, Some(&spectators), 6, 1));
+    }
+
+    #[test]
+    fn remove_owners_on_disconnect() {
+        let mut app = App::new();
+        app.init_resource::<NetRelay>()
+            .add_event::<ClientDisconnected>()
+            .add_system(remove_relay_owners);
+        let mut relay = app.world.resource_mut::<NetRelay>();
+        relay.set_owner(5, 1);
+        relay.set_owner(6, 1);
+        relay.set_owner(7, 2);
+
+        app.world.send_event(ClientDisconnected {
+            cid: 1,
+            status: Status::Closed,
+        });
+        app.update();
+
+        let relay = app.world.resource::<NetRelay>();
+        assert_eq!(relay.owned_by(1).count(), 0);
+        assert_eq!(relay.owner(7), Some(2));
+    }
+}
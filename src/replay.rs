@@ -0,0 +1,758 @@
+//! Recording and replaying network sessions.
+//!
+//! When the [`NetRecorder`] resource exists, every message of the types that `bevy-pigeon`
+//! registers (and of the types passed to [`record_msg`](crate::AppExt::record_msg)) that is
+//! received is written to a file, one [`NetRecord`] per line, along with when it was received and
+//! from who. The messages that `bevy-pigeon` sends are recorded too, along with who they were
+//! sent to.
+//!
+//! When the [`NetReplay`] resource exists, and there is no `Client` or `Server`, the received
+//! messages in the recording are applied at the same times that they were originally received,
+//! following bevy's [`Time`]. This lets a recorded match be re-watched or debugged with a client
+//! `App`, without a server. Components, bundles, resources, reflected components and despawns are
+//! replayed. Audio cues, and the clock and protocol messages, are recorded but not replayed.
+
+#[cfg(feature = "types")]
+use crate::asset::{NetAssetComp, NetAssetConv, NetAssetKeys};
+use crate::bundle::{NetBundle, NetBundleComps};
+use crate::client_entities::NetDespawn;
+#[cfg(feature = "types")]
+use crate::material::NetMaterialComp;
+#[cfg(feature = "reflect")]
+use crate::reflect::{apply_reflect_msgs, NetReflectMsg};
+use crate::res::{write_res, NetResMsg};
+use crate::sync::{CNetDir, NetComp, NetCompMsg, NetEntity};
+#[cfg(feature = "animation")]
+use crate::types::NetAnimationState;
+#[cfg(feature = "types")]
+use crate::types::{NetMaterial, NetWorldTransform};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use carrier_pigeon::{CId, Client, Server};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, Any};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Whether a [`NetRecord`] was sent or received.
+#[derive(Serialize, Deserialize, Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum RecordDir {
+    /// The message was received.
+    In,
+    /// The message was sent.
+    Out,
+}
+
+/// A recorded message.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NetRecord {
+    /// The time that the message was sent or received, in seconds since the recording started.
+    pub time: f64,
+    /// Whether the message was sent or received.
+    pub dir: RecordDir,
+    /// The [`CId`]s of the peers that the message was sent to, or the [`CId`] of the peer that
+    /// it was received from.
+    ///
+    /// This is empty for the messages that a client sent to the server.
+    pub cids: Vec<CId>,
+    /// The type name of the message.
+    pub msg: String,
+    /// The message, serialized with `ron`.
+    pub data: String,
+}
+
+/// Records network messages to a file.
+///
+/// Insert this resource to start recording, and remove it to stop.
+#[derive(Resource, Debug)]
+pub struct NetRecorder {
+    writer: Mutex<BufWriter<File>>,
+    start: Instant,
+}
+
+impl NetRecorder {
+    /// Creates a new [`NetRecorder`] that writes to the file at `path`, replacing it if it
+    /// exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(NetRecorder {
+            writer: Mutex::new(BufWriter::new(File::create(path)?)),
+            start: Instant::now(),
+        })
+    }
+
+    /// Records `msg` of type `T`.
+    ///
+    /// This can be used to record the messages that you send yourself.
+    pub fn record<T: Serialize>(&self, dir: RecordDir, cids: Vec<CId>, msg: &T) {
+        let data = match ron::to_string(msg) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+        let record = NetRecord {
+            time: self.start.elapsed().as_secs_f64(),
+            dir,
+            cids,
+            msg: type_name::<T>().to_owned(),
+            data,
+        };
+        let result = ron::to_string(&record)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            .and_then(|line| {
+                let mut writer = self.writer.lock().unwrap();
+                writeln!(writer, "{}", line)
+            });
+        if let Err(e) = result {
+            error!("{}", e);
+        }
+    }
+
+    /// Flushes the recorded messages to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+}
+
+/// Replays a recording made by a [`NetRecorder`].
+///
+/// Insert this resource to start the replay. The replay is driven by bevy's [`Time`], starting
+/// from when it is inserted, and can be paused and seeked.
+#[derive(Resource, Clone, Debug)]
+pub struct NetReplay {
+    records: Vec<NetRecord>,
+    /// The indices of the received records of each message type, in order of time.
+    index: HashMap<String, Vec<usize>>,
+    elapsed: f64,
+    paused: bool,
+}
+
+impl NetReplay {
+    /// Loads the recording from the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut records = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let record =
+                ron::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            records.push(record);
+        }
+        Ok(Self::new(records))
+    }
+
+    /// Creates a new [`NetReplay`] from `records`.
+    pub fn new(mut records: Vec<NetRecord>) -> Self {
+        records.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let mut index: HashMap<String, Vec<usize>> = HashMap::default();
+        for (i, record) in records.iter().enumerate() {
+            if record.dir == RecordDir::In {
+                index.entry(record.msg.clone()).or_default().push(i);
+            }
+        }
+        NetReplay {
+            records,
+            index,
+            elapsed: 0.0,
+            paused: false,
+        }
+    }
+
+    /// The time since the replay started, in seconds.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Moves the replay forward by `secs`, unless it is paused.
+    ///
+    /// This is called with the time's delta every frame by the replay systems.
+    pub fn advance(&mut self, secs: f64) {
+        if !self.paused {
+            self.elapsed += secs;
+        }
+    }
+
+    /// Moves the replay to `secs` after the start.
+    ///
+    /// When seeking backwards, the latest message before `secs` is applied again.
+    pub fn seek(&mut self, secs: f64) {
+        self.elapsed = secs;
+    }
+
+    /// Pauses the replay.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes the replay.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns true if the replay is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Gets the records, in order of time.
+    pub fn records(&self) -> &[NetRecord] {
+        &self.records
+    }
+
+    /// Gets the received messages of type `T` that were recorded after `from` and up to `to`,
+    /// along with the [`CId`] they were received from.
+    pub fn recv_between<T: DeserializeOwned>(&self, from: f64, to: f64) -> Vec<(CId, T)> {
+        let indices = match self.index.get(type_name::<T>()) {
+            Some(indices) => indices,
+            None => return vec![],
+        };
+        let start = indices.partition_point(|&i| self.records[i].time <= from);
+        let end = indices.partition_point(|&i| self.records[i].time <= to);
+        indices[start..end.max(start)]
+            .iter()
+            .map(|&i| &self.records[i])
+            .filter_map(|r| match ron::from_str(&r.data) {
+                Ok(msg) => Some((r.cids.first().copied().unwrap_or_default(), msg)),
+                Err(e) => {
+                    error!("{}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// A system that moves the [`NetReplay`] forward with bevy's [`Time`].
+///
+/// This only runs when there is no `Client` or `Server`. It is added by the `AppExt` methods when
+/// the `replay` feature is enabled.
+pub fn advance_replay(
+    time: Res<Time>,
+    replay: Option<ResMut<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+) {
+    if let Some(mut replay) = replay {
+        if server.is_none() && client.is_none() {
+            replay.advance(time.delta_seconds_f64());
+        }
+    }
+}
+
+/// Marks that the [`advance_replay`] system was added, so that it is only added once.
+#[derive(Resource)]
+pub(crate) struct ReplayAdded;
+
+/// A system that records the received messages of type `T`.
+///
+/// This is added for every message type that `bevy-pigeon` registers. For your own message types,
+/// call [`record_msg`](crate::AppExt::record_msg) which will add this system.
+pub fn record_recv<T: Any + Send + Sync + Serialize>(
+    recorder: Option<Res<NetRecorder>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+) {
+    let recorder = match recorder {
+        Some(recorder) => recorder,
+        None => return,
+    };
+    if let Some(server) = server {
+        for msg in server.recv::<T>() {
+            recorder.record(RecordDir::In, vec![msg.cid], &*msg);
+        }
+    } else if let Some(client) = client {
+        for msg in client.recv::<T>() {
+            recorder.record(RecordDir::In, vec![msg.cid], &*msg);
+        }
+    }
+}
+
+/// Gets the recorded messages of type `T` that a replay system should apply, or `None` if it
+/// isn't replaying.
+///
+/// `last` is the replay time of the system's last run. These are the messages since then, or all
+/// of the messages up to now after seeking backwards, so that the latest ones are applied again.
+fn replay_msgs<T: DeserializeOwned>(
+    replay: Option<&NetReplay>,
+    connected: bool,
+    last: &mut Option<f64>,
+) -> Option<Vec<(CId, T)>> {
+    let replay = match replay {
+        Some(replay) if !connected => replay,
+        _ => return None,
+    };
+    let now = replay.elapsed();
+    let from = match *last {
+        Some(last) if last <= now => last,
+        _ => f64::NEG_INFINITY,
+    };
+    *last = Some(now);
+    Some(replay.recv_between::<T>(from, now))
+}
+
+/// Gets the latest of `msgs` for the [`NetEntity`] with `id`.
+fn latest<M: Any + Send + Sync>(msgs: &[(CId, NetCompMsg<M>)], id: u64) -> Option<&M> {
+    msgs.iter()
+        .rev()
+        .find(|(_, msg)| msg.id == id)
+        .map(|(_, msg)| &msg.msg)
+}
+
+/// A system that applies the recorded messages of type `M` to component `T`, when replaying.
+///
+/// This only runs when there is a [`NetReplay`] resource, and no `Client` or `Server`.
+///
+/// Most of the time, you will call [`sync_comp`](crate::AppExt::sync_comp) which will add this
+/// system.
+pub fn replay_recv<T, M>(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut last: Local<Option<f64>>,
+    mut q: Query<(&NetEntity, &NetComp<T, M>, &mut T)>,
+) where
+    T: Clone + Into<M> + Component,
+    M: Clone + Into<T> + Any + Send + Sync + DeserializeOwned,
+{
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetCompMsg<M>>(replay.as_deref(), connected, &mut last) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    for (net_e, net_c, mut comp) in q.iter_mut() {
+        if net_c.c_dir != CNetDir::From {
+            continue;
+        }
+        if let Some(msg) = latest(&msgs, net_e.id) {
+            *comp = msg.clone().into();
+        }
+    }
+}
+
+/// A system that applies the recorded messages of type `M` to the components in `B`, when
+/// replaying.
+///
+/// Most of the time, you will call [`sync_bundle`](crate::AppExt::sync_bundle) which will add
+/// this system.
+pub fn replay_bundle<B, M>(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut last: Local<Option<f64>>,
+    mut q: Query<(&NetEntity, &NetBundle<B, M>, B::Mut)>,
+) where
+    B: NetBundleComps + Into<M>,
+    M: Clone + Into<B> + Any + Send + Sync + DeserializeOwned,
+{
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetCompMsg<M>>(replay.as_deref(), connected, &mut last) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    for (net_e, net_b, comps) in q.iter_mut() {
+        if net_b.c_dir != CNetDir::From {
+            continue;
+        }
+        if let Some(msg) = latest(&msgs, net_e.id) {
+            let bundle: B = msg.clone().into();
+            bundle.write(comps);
+        }
+    }
+}
+
+/// A system that applies the recorded messages of type `M` to component `T`, converted with the
+/// [`NetAssetConv`], when replaying.
+///
+/// Most of the time, you will call [`sync_comp_assets`](crate::AppExt::sync_comp_assets) which
+/// will add this system.
+#[cfg(feature = "types")]
+pub fn replay_asset_comp<T, M>(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    mut last: Local<Option<f64>>,
+    mut q: Query<(&NetEntity, &NetAssetComp<T, M>, &mut T)>,
+) where
+    T: Component,
+    M: NetAssetConv<T> + Clone + Any + Send + Sync + DeserializeOwned,
+{
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetCompMsg<M>>(replay.as_deref(), connected, &mut last) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    for (net_e, net_c, mut comp) in q.iter_mut() {
+        if net_c.c_dir != CNetDir::From {
+            continue;
+        }
+        let new =
+            latest(&msgs, net_e.id).and_then(|msg| msg.clone().into_comp(assets.as_deref(), &keys));
+        if let Some(new) = new {
+            *comp = new;
+        }
+    }
+}
+
+/// A system that applies the recorded animation states, when replaying.
+///
+/// Most of the time, you will call [`sync_animation`](crate::AppExt::sync_animation) which will
+/// add this system.
+#[cfg(feature = "animation")]
+pub fn replay_animation(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    assets: Option<Res<AssetServer>>,
+    keys: Res<NetAssetKeys>,
+    mut last: Local<Option<f64>>,
+    mut q: Query<(
+        &NetEntity,
+        &NetAssetComp<AnimationPlayer, NetAnimationState>,
+        &mut AnimationPlayer,
+    )>,
+) {
+    let connected = server.is_some() || client.is_some();
+    let msgs =
+        match replay_msgs::<NetCompMsg<NetAnimationState>>(replay.as_deref(), connected, &mut last)
+        {
+            Some(msgs) => msgs,
+            None => return,
+        };
+    for (net_e, net_c, mut player) in q.iter_mut() {
+        if net_c.c_dir != CNetDir::From {
+            continue;
+        }
+        if let Some(state) = latest(&msgs, net_e.id) {
+            if let Some(clip) = state.clip.to_handle(assets.as_deref(), &keys) {
+                state.apply(&mut player, clip);
+            }
+        }
+    }
+}
+
+/// A system that applies the recorded world transforms, when replaying.
+///
+/// Most of the time, you will call [`sync_world_transform`](crate::AppExt::sync_world_transform)
+/// which will add this system.
+#[cfg(feature = "types")]
+#[allow(clippy::type_complexity)]
+pub fn replay_world_transform(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut last: Local<Option<f64>>,
+    mut q: Query<(
+        &NetEntity,
+        &NetComp<GlobalTransform, NetWorldTransform>,
+        &mut Transform,
+        Option<&Parent>,
+    )>,
+    parents: Query<&GlobalTransform>,
+) {
+    let connected = server.is_some() || client.is_some();
+    let msgs =
+        match replay_msgs::<NetCompMsg<NetWorldTransform>>(replay.as_deref(), connected, &mut last)
+        {
+            Some(msgs) => msgs,
+            None => return,
+        };
+    for (net_e, net_c, mut transform, parent) in q.iter_mut() {
+        if net_c.c_dir != CNetDir::From {
+            continue;
+        }
+        if let Some(msg) = latest(&msgs, net_e.id) {
+            let parent = parent.and_then(|parent| parents.get(parent.get()).ok());
+            *transform = msg.to_local(parent);
+        }
+    }
+}
+
+/// A system that applies the recorded material parameters, when replaying.
+///
+/// Most of the time, you will call [`sync_materials`](crate::AppExt::sync_materials) which will
+/// add this system.
+#[cfg(feature = "types")]
+pub fn replay_material(
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut last: Local<Option<f64>>,
+    q: Query<(&NetEntity, &NetMaterialComp, &Handle<StandardMaterial>)>,
+) {
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetCompMsg<NetMaterial>>(replay.as_deref(), connected, &mut last)
+    {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    for (net_e, net_c, handle) in q.iter() {
+        if net_c.c_dir != CNetDir::From {
+            continue;
+        }
+        if let (Some(msg), Some(material)) = (latest(&msgs, net_e.id), materials.get_mut(handle)) {
+            msg.apply(material);
+        }
+    }
+}
+
+/// A system that applies the recorded messages of type `M` to resource `R`, when replaying.
+///
+/// The resource is inserted if it doesn't exist.
+///
+/// Most of the time, you will call [`sync_res`](crate::AppExt::sync_res) which will add this
+/// system.
+pub fn replay_res<R, M>(
+    mut commands: Commands,
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    res: Option<ResMut<R>>,
+    mut last: Local<Option<f64>>,
+) where
+    R: Resource + Clone + Into<M>,
+    M: Clone + Into<R> + Any + Send + Sync + DeserializeOwned,
+{
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetResMsg<M>>(replay.as_deref(), connected, &mut last) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    if let Some((_, latest)) = msgs.into_iter().last() {
+        write_res(&mut commands, res, latest.msg.into());
+    }
+}
+
+/// A system that despawns the entities that the recording says to, when replaying.
+///
+/// Most of the time, you will call
+/// [`despawn_client_entities`](crate::AppExt::despawn_client_entities) which will add this
+/// system.
+pub fn replay_despawns(
+    mut commands: Commands,
+    replay: Option<Res<NetReplay>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    mut last: Local<Option<f64>>,
+    q: Query<(Entity, &NetEntity)>,
+) {
+    let connected = server.is_some() || client.is_some();
+    let msgs = match replay_msgs::<NetDespawn>(replay.as_deref(), connected, &mut last) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    for (_, msg) in msgs {
+        for (entity, _) in q.iter().filter(|(_, net_e)| net_e.id == msg.id) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// A system that applies the recorded reflected components, when replaying.
+///
+/// Most of the time, you will call [`sync_reflect`](crate::AppExt::sync_reflect) which will add
+/// this system.
+#[cfg(feature = "reflect")]
+pub fn replay_reflect(
+    world: &mut World,
+    mut last_replay: Local<Option<f64>>,
+    mut last: Local<HashMap<(Entity, String), u32>>,
+) {
+    let connected = world.contains_resource::<Server>() || world.contains_resource::<Client>();
+    let msgs = match replay_msgs::<NetReflectMsg>(
+        world.get_resource::<NetReplay>(),
+        connected,
+        &mut last_replay,
+    ) {
+        Some(msgs) => msgs,
+        None => return,
+    };
+    // The recording doesn't have the send times, so the messages are applied in order.
+    let msgs = msgs
+        .into_iter()
+        .map(|(cid, msg)| (cid, None, msg))
+        .collect();
+    apply_reflect_msgs(world, &mut last, msgs, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::record_sent;
+    use crate::AppExt;
+    use carrier_pigeon::{MsgTable, Transport};
+
+    #[derive(Component, Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
+    struct Score(u32);
+
+    fn record(time: f64, id: u64, score: u32) -> NetRecord {
+        NetRecord {
+            time,
+            dir: RecordDir::In,
+            cids: vec![1],
+            msg: type_name::<NetCompMsg<Score>>().to_owned(),
+            data: ron::to_string(&NetCompMsg::new(id, Score(score))).unwrap(),
+        }
+    }
+
+    #[derive(Resource, Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
+    struct Round(u32);
+
+    fn record_msg<T: Serialize>(time: f64, msg: &T) -> NetRecord {
+        NetRecord {
+            time,
+            dir: RecordDir::In,
+            cids: vec![0],
+            msg: type_name::<T>().to_owned(),
+            data: ron::to_string(msg).unwrap(),
+        }
+    }
+
+    #[test]
+    fn record_then_replay() {
+        let path = std::env::temp_dir().join(format!("bevy-pigeon-{}.ron", std::process::id()));
+        let recorder = NetRecorder::create(&path).unwrap();
+        recorder.record(RecordDir::In, vec![1], &NetCompMsg::new(5, Score(10)));
+        recorder.record(RecordDir::Out, vec![2], &NetCompMsg::new(5, Score(20)));
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let mut replay = NetReplay::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.records().len(), 2);
+
+        replay.advance(60.0);
+        // Only the received message is replayed.
+        let msgs = replay.recv_between::<NetCompMsg<Score>>(f64::NEG_INFINITY, replay.elapsed());
+        assert_eq!(msgs, vec![(1, NetCompMsg::new(5, Score(10)))]);
+    }
+
+    #[test]
+    fn recv_between() {
+        let replay = NetReplay::new(vec![
+            record(2.0, 5, 2),
+            record(1.0, 5, 1),
+            record(3.0, 5, 3),
+        ]);
+        let scores = |from, to| -> Vec<u32> {
+            replay
+                .recv_between::<NetCompMsg<Score>>(from, to)
+                .into_iter()
+                .map(|(_, msg)| msg.msg.0)
+                .collect()
+        };
+        assert_eq!(scores(0.0, 1.0), vec![1]);
+        assert_eq!(scores(1.0, 3.0), vec![2, 3]);
+        assert_eq!(scores(3.0, 10.0), Vec::<u32>::new());
+        assert!(replay.recv_between::<NetCompMsg<u32>>(0.0, 10.0).is_empty());
+    }
+
+    #[test]
+    fn pause_and_seek() {
+        let mut replay = NetReplay::new(vec![]);
+        replay.advance(1.0);
+        replay.pause();
+        replay.advance(1.0);
+        assert_eq!(replay.elapsed(), 1.0);
+        replay.resume();
+        replay.advance(0.5);
+        assert_eq!(replay.elapsed(), 1.5);
+        replay.seek(0.25);
+        assert_eq!(replay.elapsed(), 0.25);
+    }
+
+    #[test]
+    fn replay_applies_comp() {
+        let mut app = App::new();
+        app.insert_resource(Time::default())
+            .sync_comp::<Score, Score>(&mut MsgTable::new(), Transport::TCP)
+            .insert_resource(NetReplay::new(vec![record(0.5, 5, 10), record(2.0, 5, 20)]));
+        let entity = app
+            .world
+            .spawn((NetEntity::new(5), NetComp::<Score>::default(), Score(0)))
+            .id();
+
+        app.update();
+        assert_eq!(app.world.get::<Score>(entity), Some(&Score(0)));
+
+        app.world.resource_mut::<NetReplay>().seek(1.0);
+        app.update();
+        assert_eq!(app.world.get::<Score>(entity), Some(&Score(10)));
+
+        app.world.resource_mut::<NetReplay>().seek(5.0);
+        app.update();
+        assert_eq!(app.world.get::<Score>(entity), Some(&Score(20)));
+
+        // Seeking backwards applies the latest message before that time again.
+        app.world.resource_mut::<NetReplay>().seek(1.0);
+        app.update();
+        assert_eq!(app.world.get::<Score>(entity), Some(&Score(10)));
+    }
+
+    #[test]
+    fn record_sent_msgs() {
+        let path =
+            std::env::temp_dir().join(format!("bevy-pigeon-sent-{}.ron", std::process::id()));
+        let recorder = NetRecorder::create(&path).unwrap();
+        record_sent(
+            Some(&recorder),
+            || vec![1, 2],
+            &NetCompMsg::new(5, Score(10)),
+        );
+        record_sent(None, || unreachable!(), &NetCompMsg::new(5, Score(20)));
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let replay = NetReplay::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.records().len(), 1);
+        assert_eq!(replay.records()[0].dir, RecordDir::Out);
+        assert_eq!(replay.records()[0].cids, vec![1, 2]);
+    }
+
+    #[test]
+    fn replay_applies_res() {
+        let mut app = App::new();
+        app.insert_resource(Time::default())
+            .sync_res::<Round, Round>(&mut MsgTable::new(), Transport::TCP)
+            .insert_resource(NetReplay::new(vec![
+                record_msg(0.5, &NetResMsg { msg: Round(1) }),
+                record_msg(2.0, &NetResMsg { msg: Round(2) }),
+            ]));
+
+        app.update();
+        assert_eq!(app.world.get_resource::<Round>(), None);
+
+        app.world.resource_mut::<NetReplay>().seek(1.0);
+        app.update();
+        assert_eq!(app.world.get_resource::<Round>(), Some(&Round(1)));
+
+        app.world.resource_mut::<NetReplay>().seek(5.0);
+        app.update();
+        assert_eq!(app.world.get_resource::<Round>(), Some(&Round(2)));
+    }
+
+    #[test]
+    fn replay_despawns_entities() {
+        let mut app = App::new();
+        app.insert_resource(Time::default())
+            .despawn_client_entities(&mut MsgTable::new())
+            .insert_resource(NetReplay::new(vec![record_msg(1.0, &NetDespawn { id: 5 })]));
+        let despawned = app.world.spawn(NetEntity::new(5)).id();
+        let kept = app.world.spawn(NetEntity::new(6)).id();
+
+        app.world.resource_mut::<NetReplay>().seek(2.0);
+        app.update();
+        assert!(app.world.get_entity(despawned).is_none());
+        assert!(app.world.get_entity(kept).is_some());
+    }
+}
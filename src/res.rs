@@ -0,0 +1,159 @@
+//! Syncing resources.
+//!
+//! Resources, like [`AmbientLight`] or `ClearColor`, are synced from the server to every client.
+//! The resource is sent whenever it changes, and to every client that connects, so the clients
+//! always have the server's value.
+
+use crate::app::Recorder;
+use crate::spectator::{send_spec, Spectators};
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{CId, Client, Server};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+
+/// The message type that carries a resource.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct NetResMsg<M: Any + Send + Sync> {
+    pub(crate) msg: M,
+}
+
+/// A system that sends resource `R` using message type `M`, from the server to the clients.
+///
+/// The resource is sent to every client when it changes, and to new clients when they connect.
+///
+/// Most of the time, you will call [`sync_res`](crate::AppExt::sync_res) which will add this
+/// system.
+pub fn res_send<R, M>(
+    server: Option<Res<Server>>,
+    spectators: Option<Res<Spectators>>,
+    recorder: Option<Res<Recorder>>,
+    res: Option<Res<R>>,
+    mut known: Local<HashSet<CId>>,
+) where
+    R: Resource + Clone + Into<M>,
+    M: Clone + Into<R> + Any + Send + Sync + Serialize,
+{
+    let (server, res) = match (server, res) {
+        (Some(server), Some(res)) => (server, res),
+        _ => return,
+    };
+    let spectators = spectators.as_deref();
+    let recorder = recorder.as_deref();
+    let specs = send_specs(
+        res.is_changed(),
+        &mut known,
+        &server.cids().collect::<Vec<_>>(),
+    );
+    if specs.is_empty() {
+        return;
+    }
+    let msg = NetResMsg::<M> {
+        msg: (*res).clone().into(),
+    };
+    for spec in specs {
+        send_spec(&server, spectators, recorder, spec, &msg);
+    }
+}
+
+/// Gets who the resource should be sent to.
+///
+/// This is everyone if it `changed`, otherwise it is the `cids` that are not `known` yet. The
+/// `known` clients are updated to the connected `cids`.
+fn send_specs(changed: bool, known: &mut HashSet<CId>, cids: &[CId]) -> Vec<CIdSpec> {
+    let new_cids: Vec<CId> = cids
+        .iter()
+        .copied()
+        .filter(|cid| !known.contains(cid))
+        .collect();
+    known.extend(new_cids.iter().copied());
+    known.retain(|cid| cids.contains(cid));
+
+    if changed {
+        vec![CIdSpec::All]
+    } else {
+        new_cids.into_iter().map(CIdSpec::Only).collect()
+    }
+}
+
+/// A system that receives resource `R` using message type `M`, on the clients.
+///
+/// The resource is inserted if it doesn't exist.
+///
+/// Most of the time, you will call [`sync_res`](crate::AppExt::sync_res) which will add this
+/// system.
+pub fn res_recv<R, M>(mut commands: Commands, client: Option<Res<Client>>, res: Option<ResMut<R>>)
+where
+    R: Resource + Clone + Into<M>,
+    M: Clone + Into<R> + Any + Send + Sync,
+{
+    let client = match client {
+        Some(client) => client,
+        None => return,
+    };
+    if let Some(latest) = client.recv::<NetResMsg<M>>().last() {
+        write_res(&mut commands, res, latest.msg.clone().into());
+    }
+}
+
+/// Overwrites the resource with `new`, or inserts it if it doesn't exist.
+pub(crate) fn write_res<R: Resource>(commands: &mut Commands, res: Option<ResMut<R>>, new: R) {
+    match res {
+        Some(mut res) => *res = new,
+        None => commands.insert_resource(new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Clone, Eq, PartialEq, Debug)]
+    struct Round(u32);
+
+    #[test]
+    fn send_on_change() {
+        let mut known = HashSet::default();
+        known.extend([1, 2]);
+        assert_eq!(send_specs(true, &mut known, &[1, 2]), vec![CIdSpec::All]);
+        assert!(send_specs(false, &mut known, &[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn send_to_new_cids() {
+        let mut known = HashSet::default();
+        known.insert(1);
+        assert_eq!(
+            send_specs(false, &mut known, &[1, 3]),
+            vec![CIdSpec::Only(3)]
+        );
+        assert!(send_specs(false, &mut known, &[1, 3]).is_empty());
+
+        // Clients that reconnect are sent the resource again.
+        assert!(send_specs(false, &mut known, &[3]).is_empty());
+        assert_eq!(
+            send_specs(false, &mut known, &[1, 3]),
+            vec![CIdSpec::Only(1)]
+        );
+
+        // A change is sent to everyone, and new clients are known after it.
+        assert_eq!(send_specs(true, &mut known, &[1, 3, 4]), vec![CIdSpec::All]);
+        assert!(send_specs(false, &mut known, &[1, 3, 4]).is_empty());
+    }
+
+    #[test]
+    fn recv_inserts_then_overwrites() {
+        fn recv(mut commands: Commands, res: Option<ResMut<Round>>, mut next: Local<u32>) {
+            *next += 1;
+            write_res(&mut commands, res, Round(*next));
+        }
+
+        let mut app = App::new();
+        app.add_system(recv);
+        app.update();
+        assert_eq!(app.world.get_resource::<Round>(), Some(&Round(1)));
+        app.update();
+        assert_eq!(app.world.get_resource::<Round>(), Some(&Round(2)));
+    }
+}
@@ -0,0 +1,292 @@
+//! Spectator (observer) clients.
+//!
+//! A spectator is a connected client that receives everything, but is not allowed to send
+//! anything. The server ignores all component messages from spectators, and can optionally delay
+//! the messages sent to them, so they can't be used to give other players an unfair advantage.
+
+use crate::app::{record_sent, Recorder};
+use crate::config::NetConfig;
+use crate::connection::ClientDisconnected;
+use crate::NetLabel;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use carrier_pigeon::net::CIdSpec;
+use carrier_pigeon::{CId, Server};
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A message waiting to be sent to a spectator.
+type Delayed = (Instant, Box<dyn FnOnce(&Server) + Send>);
+
+/// The connected clients that are spectators.
+///
+/// This is only used on the server. Add the [`CId`] of a client when it connects to make it a
+/// spectator; it is removed automatically when it disconnects.
+#[derive(Resource)]
+pub struct Spectators {
+    cids: HashSet<CId>,
+    delay: Option<Duration>,
+    queue: Mutex<VecDeque<Delayed>>,
+}
+
+impl Default for Spectators {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Debug for Spectators {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spectators")
+            .field("cids", &self.cids)
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+impl Spectators {
+    /// Creates a new [`Spectators`], where the messages sent to spectators are delayed by
+    /// `delay`.
+    pub fn new(delay: Option<Duration>) -> Self {
+        Spectators {
+            cids: HashSet::default(),
+            delay,
+            queue: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Makes the client `cid` a spectator.
+    pub fn insert(&mut self, cid: CId) {
+        self.cids.insert(cid);
+    }
+
+    /// Makes the client `cid` a player again.
+    pub fn remove(&mut self, cid: CId) {
+        self.cids.remove(&cid);
+    }
+
+    /// Returns true if the client `cid` is a spectator.
+    pub fn contains(&self, cid: CId) -> bool {
+        self.cids.contains(&cid)
+    }
+
+    /// Gets the [`CId`]s of the spectators.
+    pub fn iter(&self) -> impl Iterator<Item = CId> + '_ {
+        self.cids.iter().copied()
+    }
+
+    /// The amount of time that messages sent to spectators are delayed by.
+    pub fn delay(&self) -> Option<Duration> {
+        self.delay
+    }
+
+    /// The number of connected spectators.
+    pub fn spectator_count(&self) -> usize {
+        self.cids.len()
+    }
+
+    /// The number of connected clients that are not spectators.
+    pub fn player_count(&self, server: &Server) -> usize {
+        self.count_players(server.cids())
+    }
+
+    /// Counts the `cids` that are not spectators.
+    fn count_players(&self, cids: impl Iterator<Item = CId>) -> usize {
+        cids.filter(|cid| !self.contains(*cid)).count()
+    }
+}
+
+/// The spectator plugin.
+///
+/// Inserts the [`Spectators`] resource with the given delay, and adds the systems that send the
+/// delayed messages and forget spectators that disconnected.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, Hash)]
+pub struct SpectatorPlugin {
+    /// The amount of time that messages sent to spectators are delayed by.
+    pub delay: Option<Duration>,
+}
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        let config = NetConfig::get(app);
+        app.insert_resource(Spectators::new(self.delay))
+            .add_event::<ClientDisconnected>()
+            .add_system_to_stage(config.send_stage, send_delayed.after(NetLabel::Send))
+            .add_system_to_stage(config.recv_stage, remove_spectators.after(NetLabel::Recv));
+    }
+}
+
+/// Sends `msg` to the clients matching `spec`.
+///
+/// If there is a delay, the message is queued for the spectators, and sent by [`send_delayed`].
+/// The message is recorded when it is queued, if there is a `recorder`.
+pub(crate) fn send_spec<T: Clone + Any + Send + Sync + Serialize>(
+    server: &Server,
+    spectators: Option<&Spectators>,
+    recorder: Option<&Recorder>,
+    spec: CIdSpec,
+    msg: &T,
+) {
+    record_sent(
+        recorder,
+        || server.cids().filter(|cid| spec.matches(*cid)).collect(),
+        msg,
+    );
+    let (spectators, delay) = match spectators {
+        Some(spectators) if !spectators.cids.is_empty() => match spectators.delay {
+            Some(delay) => (spectators, delay),
+            None => {
+                if let Err(e) = server.send_spec(spec, msg) {
+                    error!("{}", e);
+                }
+                return;
+            }
+        },
+        _ => {
+            if let Err(e) = server.send_spec(spec, msg) {
+                error!("{}", e);
+            }
+            return;
+        }
+    };
+
+    let due = Instant::now() + delay;
+    let mut queue = spectators.queue.lock().unwrap();
+    for cid in server.cids().filter(|cid| spec.matches(*cid)) {
+        if spectators.contains(cid) {
+            let msg = msg.clone();
+            queue.push_back((
+                due,
+                Box::new(move |server: &Server| {
+                    if let Err(e) = server.send_to(cid, &msg) {
+                        error!("{}", e);
+                    }
+                }),
+            ));
+        } else if let Err(e) = server.send_to(cid, msg) {
+            error!("{}", e);
+        }
+    }
+}
+
+/// Returns true if `cid` is a spectator.
+pub(crate) fn is_spectator(spectators: Option<&Spectators>, cid: CId) -> bool {
+    spectators.map_or(false, |spectators| spectators.contains(cid))
+}
+
+/// Sends the delayed messages to the spectators once they are due.
+///
+/// This is added by the [`SpectatorPlugin`].
+pub fn send_delayed(server: Option<Res<Server>>, spectators: Option<Res<Spectators>>) {
+    if let (Some(server), Some(spectators)) = (server, spectators) {
+        let now = Instant::now();
+        let mut queue = spectators.queue.lock().unwrap();
+        while let Some(send) = pop_due(&mut queue, now) {
+            send(&server);
+        }
+    }
+}
+
+/// Takes the front of `queue` if it is due at `now`.
+///
+/// The queue is in the order that the messages were sent, and they all have the same delay, so
+/// the ones behind a message that isn't due aren't due either.
+fn pop_due<T>(queue: &mut VecDeque<(Instant, T)>, now: Instant) -> Option<T> {
+    if queue.front().map_or(false, |(due, _)| *due <= now) {
+        queue.pop_front().map(|(_, item)| item)
+    } else {
+        None
+    }
+}
+
+/// Removes the spectators that disconnected.
+///
+/// This is added by the [`SpectatorPlugin`].
+pub fn remove_spectators(
+    spectators: Option<ResMut<Spectators>>,
+    mut er: EventReader<ClientDisconnected>,
+) {
+    if let Some(mut spectators) = spectators {
+        for discon in er.iter() {
+            spectators.remove(discon.cid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use carrier_pigeon::net::Status;
+
+    #[test]
+    fn delayed_until_due() {
+        let start = Instant::now();
+        let delay = Duration::from_millis(100);
+        let mut queue = VecDeque::new();
+        queue.push_back((start + delay, 1));
+        queue.push_back((start + delay, 2));
+        queue.push_back((start + Duration::from_millis(10) + delay, 3));
+
+        assert_eq!(pop_due(&mut queue, start), None);
+        assert_eq!(pop_due(&mut queue, start + Duration::from_millis(50)), None);
+
+        let now = start + delay;
+        assert_eq!(pop_due(&mut queue, now), Some(1));
+        assert_eq!(pop_due(&mut queue, now), Some(2));
+        assert_eq!(pop_due(&mut queue, now), None);
+
+        let now = start + Duration::from_millis(200);
+        assert_eq!(pop_due(&mut queue, now), Some(3));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn spectator_membership() {
+        let mut spectators = Spectators::default();
+        spectators.insert(2);
+        spectators.insert(3);
+
+        assert!(is_spectator(Some(&spectators), 2));
+        assert!(!is_spectator(Some(&spectators), 1));
+        assert!(!is_spectator(None, 2));
+
+        spectators.remove(2);
+        assert!(!is_spectator(Some(&spectators), 2));
+    }
+
+    #[test]
+    fn counts() {
+        let mut spectators = Spectators::default();
+        spectators.insert(2);
+        spectators.insert(3);
+
+        assert_eq!(spectators.spectator_count(), 2);
+        assert_eq!(spectators.count_players([1, 2, 3, 4].into_iter()), 2);
+        assert_eq!(spectators.count_players([2, 3].into_iter()), 0);
+    }
+
+    #[test]
+    fn removed_on_disconnect() {
+        let mut app = App::new();
+        app.insert_resource(Spectators::default())
+            .add_event::<ClientDisconnected>()
+            .add_system(remove_spectators);
+        let mut spectators = app.world.resource_mut::<Spectators>();
+        spectators.insert(1);
+        spectators.insert(2);
+
+        app.world.send_event(ClientDisconnected {
+            cid: 1,
+            status: Status::Closed,
+        });
+        app.update();
+
+        let spectators = app.world.resource::<Spectators>();
+        assert!(!spectators.contains(1));
+        assert!(spectators.contains(2));
+    }
+}
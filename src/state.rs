@@ -0,0 +1,225 @@
+//! An optional session state machine, integrated with bevy's [`State`].
+
+use crate::config::NetConfig;
+use crate::NetLabel;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use carrier_pigeon::{Client, OptionPendingClient, Server};
+use std::time::Duration;
+
+/// The state of the network session.
+///
+/// This is managed by the [`NetStatePlugin`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum NetState {
+    /// There is no connection.
+    Disconnected,
+    /// A client is waiting for a response from the server.
+    Connecting,
+    /// The client is connected, or the server is running.
+    Connected,
+    /// The connection was just closed.
+    ///
+    /// This state lasts for one frame before moving to [`NetState::Disconnected`], so that you
+    /// can clean up with [`SystemSet::on_enter`].
+    Disconnecting,
+}
+
+/// The amount of time a client waits for a response from the server before giving up.
+///
+/// Defaults to 10 seconds.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash)]
+pub struct ConnectTimeout(pub Duration);
+
+impl Default for ConnectTimeout {
+    fn default() -> Self {
+        ConnectTimeout(Duration::from_secs(10))
+    }
+}
+
+/// An event that is fired when a pending connection takes longer than the [`ConnectTimeout`].
+///
+/// The [`OptionPendingClient`] resource is removed when this happens.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct ConnectTimedOut;
+
+/// The session state plugin.
+///
+/// Adds the [`NetState`] state, and keeps it up to date with the `Client`, `Server` and
+/// `OptionPendingClient` resources. When this plugin is added, the systems added by
+/// [`sync_comp`](crate::AppExt::sync_comp) only run in the [`NetState::Connected`] state.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default, Hash)]
+pub struct NetStatePlugin;
+
+impl Plugin for NetStatePlugin {
+    fn build(&self, app: &mut App) {
+        let stage = NetConfig::get(app).recv_stage;
+        app.add_state(NetState::Disconnected)
+            .init_resource::<ConnectTimeout>()
+            .add_event::<ConnectTimedOut>()
+            .add_system_to_stage(stage, update_net_state.after(NetLabel::Recv));
+    }
+}
+
+/// Moves the [`NetState`] to match the networking resources.
+///
+/// This is added by the [`NetStatePlugin`].
+#[allow(clippy::too_many_arguments)]
+pub fn update_net_state(
+    mut commands: Commands,
+    mut state: ResMut<State<NetState>>,
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    pending: Option<Res<OptionPendingClient>>,
+    timeout: Res<ConnectTimeout>,
+    time: Res<Time>,
+    mut connecting_since: Local<Option<Duration>>,
+    mut ew: EventWriter<ConnectTimedOut>,
+) {
+    let current = *state.current();
+    let connected = server.is_some() || client.map_or(false, |client| client.open());
+    let connecting_for = if !connected && pending.is_some() {
+        let since = *connecting_since.get_or_insert(time.elapsed());
+        Some(time.elapsed() - since)
+    } else {
+        None
+    };
+
+    let (next, timed_out) = next_state(current, connected, connecting_for, timeout.0);
+    if timed_out {
+        debug!("Connection timed out after {:?}", timeout.0);
+        commands.remove_resource::<OptionPendingClient>();
+        ew.send(ConnectTimedOut);
+    }
+    if next != NetState::Connecting {
+        *connecting_since = None;
+    }
+    if next != current {
+        // This can only fail if a transition is already queued, in which case, we will try again
+        // next frame.
+        let _ = state.set(next);
+    }
+}
+
+/// Gets the state to move to from `current`, and whether the pending connection timed out.
+///
+/// `connecting_for` is how long the pending client has been waiting for a response, if there is
+/// one.
+fn next_state(
+    current: NetState,
+    connected: bool,
+    connecting_for: Option<Duration>,
+    timeout: Duration,
+) -> (NetState, bool) {
+    match connecting_for {
+        _ if connected => (NetState::Connected, false),
+        Some(waited) if waited > timeout => (NetState::Disconnected, true),
+        Some(_) => (NetState::Connecting, false),
+        None if current == NetState::Connected => (NetState::Disconnecting, false),
+        None => (NetState::Disconnected, false),
+    }
+}
+
+/// A run criteria that only runs in the [`NetState::Connected`] state.
+///
+/// If the [`NetStatePlugin`] is not added, this always runs.
+pub fn net_connected(state: Option<Res<State<NetState>>>) -> ShouldRun {
+    match state {
+        Some(state) if *state.current() != NetState::Connected => ShouldRun::No,
+        _ => ShouldRun::Yes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    fn count_runs(mut runs: ResMut<Runs>) {
+        runs.0 += 1;
+    }
+
+    #[test]
+    fn connect() {
+        let timeout = Duration::from_secs(10);
+        let waited = Some(Duration::from_secs(1));
+        assert_eq!(
+            next_state(NetState::Disconnected, false, None, timeout),
+            (NetState::Disconnected, false)
+        );
+        assert_eq!(
+            next_state(NetState::Disconnected, false, waited, timeout),
+            (NetState::Connecting, false)
+        );
+        assert_eq!(
+            next_state(NetState::Connecting, true, None, timeout),
+            (NetState::Connected, false)
+        );
+    }
+
+    #[test]
+    fn connect_timeout() {
+        let timeout = Duration::from_secs(10);
+        assert_eq!(
+            next_state(NetState::Connecting, false, Some(timeout), timeout),
+            (NetState::Connecting, false)
+        );
+        assert_eq!(
+            next_state(
+                NetState::Connecting,
+                false,
+                Some(Duration::from_secs(11)),
+                timeout
+            ),
+            (NetState::Disconnected, true)
+        );
+    }
+
+    #[test]
+    fn disconnecting() {
+        let mut app = App::new();
+        app.insert_resource(Time::default())
+            .add_plugin(NetStatePlugin);
+        app.world
+            .resource_mut::<State<NetState>>()
+            .overwrite_set(NetState::Connected)
+            .unwrap();
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<NetState>>().current(),
+            NetState::Connected
+        );
+
+        // There is no `Client` or `Server`, so the connection was closed.
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<NetState>>().current(),
+            NetState::Disconnecting
+        );
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<NetState>>().current(),
+            NetState::Disconnected
+        );
+    }
+
+    #[test]
+    fn connected_run_criteria() {
+        let runs = |state: Option<NetState>| {
+            let mut app = App::new();
+            app.init_resource::<Runs>()
+                .add_system(count_runs.with_run_criteria(net_connected));
+            if let Some(state) = state {
+                app.insert_resource(State::new(state));
+            }
+            app.update();
+            app.world.resource::<Runs>().0
+        };
+        assert_eq!(runs(None), 1);
+        assert_eq!(runs(Some(NetState::Disconnected)), 0);
+        assert_eq!(runs(Some(NetState::Connecting)), 0);
+        assert_eq!(runs(Some(NetState::Connected)), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Types in this file:
+//! - [AnimationPlayer]
+
+use crate::asset::{NetAssetConv, NetAssetKeys};
+use crate::types::NetHandle;
+use bevy::prelude::*;
+use bevy::reflect::Struct;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The network-able version of the playback state of an [AnimationPlayer].
+///
+/// The clip is sent as a [`NetHandle`], so this is converted with [`NetAssetConv`] instead of
+/// `From`. Sync it with [`sync_animation`](crate::AppExt::sync_animation), which also seeks
+/// forward to make up for the latency.
+///
+/// [AnimationPlayer] has no getters for its clip and repeat flag, so they are read through
+/// reflection, by the names of its private fields in bevy 0.9. If they are missing, the state
+/// isn't sent, and a warning is logged the first time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetAnimationState {
+    /// The animation clip that is playing.
+    pub clip: NetHandle<AnimationClip>,
+    /// The time elapsed playing the clip, in seconds.
+    pub elapsed: f32,
+    /// The speed of the playback.
+    pub speed: f32,
+    /// Whether the playback is paused.
+    pub paused: bool,
+    /// Whether the clip starts over when it ends.
+    pub repeat: bool,
+}
+
+impl NetAnimationState {
+    /// Moves the playback forward by `secs`, as it would have while playing.
+    pub fn advance(&mut self, secs: f32) {
+        if !self.paused {
+            self.elapsed += secs * self.speed;
+        }
+    }
+
+    /// Applies this state to `player`, playing `clip`, which is the resolved [`NetHandle`].
+    ///
+    /// The player is only restarted if it isn't already playing `clip`, so the rest of its state
+    /// is kept.
+    pub fn apply(&self, player: &mut AnimationPlayer, clip: Handle<AnimationClip>) {
+        if player_field::<Handle<AnimationClip>>(player, "animation_clip") != Some(&clip) {
+            player.start(clip);
+        }
+        player.set_elapsed(self.elapsed).set_speed(self.speed);
+        if self.repeat {
+            player.repeat();
+        } else {
+            player.stop_repeating();
+        }
+        if self.paused {
+            player.pause();
+        } else {
+            player.resume();
+        }
+    }
+}
+
+/// Whether a missing field of [AnimationPlayer] was warned about.
+static WARNED_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Reads the private field `name` of `player` through reflection.
+///
+/// Logs a warning the first time a field is missing, like if a bevy update renamed it.
+fn player_field<'a, T: Reflect>(player: &'a AnimationPlayer, name: &str) -> Option<&'a T> {
+    let field = player
+        .field(name)
+        .and_then(|field| field.downcast_ref::<T>());
+    if field.is_none() && !WARNED_MISSING.swap(true, Ordering::Relaxed) {
+        warn!(
+            "`AnimationPlayer` has no `{}` field of type `{}`. `NetAnimationState` depends on the fields of bevy 0.9's `AnimationPlayer`.",
+            name,
+            std::any::type_name::<T>()
+        );
+    }
+    field
+}
+
+impl NetAssetConv<AnimationPlayer> for NetAnimationState {
+    fn from_comp(
+        comp: &AnimationPlayer,
+        assets: Option<&AssetServer>,
+        keys: &NetAssetKeys,
+    ) -> Option<Self> {
+        let clip = player_field::<Handle<AnimationClip>>(comp, "animation_clip")?;
+        let repeat = *player_field::<bool>(comp, "repeat")?;
+        Some(NetAnimationState {
+            clip: NetHandle::from_handle(clip, assets, keys)?,
+            elapsed: comp.elapsed(),
+            speed: comp.speed(),
+            paused: comp.is_paused(),
+            repeat,
+        })
+    }
+
+    fn into_comp(
+        self,
+        assets: Option<&AssetServer>,
+        keys: &NetAssetKeys,
+    ) -> Option<AnimationPlayer> {
+        let mut player = AnimationPlayer::default();
+        self.apply(&mut player, self.clip.to_handle(assets, keys)?);
+        Some(player)
+    }
+}
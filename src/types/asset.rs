@@ -0,0 +1,103 @@
+//! Types in this file:
+//! - [Handle]
+
+use crate::asset::NetAssetKeys;
+use bevy::asset::Asset;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+
+/// How a [`NetHandle`] refers to an asset.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum NetAssetRef {
+    /// A key registered in the [`NetAssetKeys`] resource.
+    Key(String),
+    /// The asset path, as given to [`AssetServer::load`], including the label if there is one.
+    Path(String),
+}
+
+/// The network-able version of [Handle].
+///
+/// Handles are local ids, so they are sent as a key registered in the [`NetAssetKeys`] resource,
+/// or as the asset path of the handle. Since asset paths need the [`AssetServer`], it can't be
+/// converted with `From`; use [`NetHandle::from_handle`] and [`NetHandle::to_handle`], or sync it
+/// with [`sync_comp_assets`](crate::AppExt::sync_comp_assets).
+#[derive(Serialize, Deserialize)]
+pub struct NetHandle<T> {
+    pub asset: NetAssetRef,
+    #[serde(skip)]
+    _pd: PhantomData<fn() -> T>,
+}
+
+impl<T> NetHandle<T> {
+    /// Creates a new [`NetHandle`] that refers to `asset`.
+    pub fn new(asset: NetAssetRef) -> Self {
+        NetHandle {
+            asset,
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<T: Asset> NetHandle<T> {
+    /// Gets the [`NetHandle`] of `handle`.
+    ///
+    /// The key in `keys` is used if there is one, otherwise the asset path. Returns `None` if
+    /// the handle has neither, like handles made with [`Assets::add`]. Without an
+    /// [`AssetServer`], like on a headless server without the `AssetPlugin`, only keys are used.
+    pub fn from_handle(
+        handle: &Handle<T>,
+        assets: Option<&AssetServer>,
+        keys: &NetAssetKeys,
+    ) -> Option<Self> {
+        if let Some(key) = keys.key(handle) {
+            return Some(NetHandle::new(NetAssetRef::Key(key.to_owned())));
+        }
+        let path = assets?.get_handle_path(handle)?;
+        let mut path_str = path.path().to_string_lossy().into_owned();
+        if let Some(label) = path.label() {
+            path_str.push('#');
+            path_str.push_str(label);
+        }
+        Some(NetHandle::new(NetAssetRef::Path(path_str)))
+    }
+
+    /// Resolves this into a [`Handle`].
+    ///
+    /// Asset paths are loaded with the [`AssetServer`], which does nothing if the asset is
+    /// already loaded. Returns `None` if the key is not registered in `keys` for an asset of type
+    /// `T`, or if this is an asset path and there is no [`AssetServer`].
+    pub fn to_handle(
+        &self,
+        assets: Option<&AssetServer>,
+        keys: &NetAssetKeys,
+    ) -> Option<Handle<T>> {
+        match &self.asset {
+            NetAssetRef::Key(key) => keys.get_typed(key),
+            NetAssetRef::Path(path) => assets.map(|assets| assets.load(path.as_str())),
+        }
+    }
+}
+
+impl<T> Clone for NetHandle<T> {
+    fn clone(&self) -> Self {
+        NetHandle::new(self.asset.clone())
+    }
+}
+
+impl<T> PartialEq for NetHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.asset == other.asset
+    }
+}
+
+impl<T> Eq for NetHandle<T> {}
+
+impl<T> Debug for NetHandle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct(&format!("NetHandle<{}>", std::any::type_name::<T>()))
+            .field("asset", &self.asset)
+            .finish()
+    }
+}
@@ -0,0 +1,146 @@
+//! Types in this file:
+//! - [PerspectiveProjection]
+//! - [Projection]
+//! - [Camera]
+//! - [Viewport]
+
+use crate::types::misc::NetOrthographicProjection;
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use serde::{Deserialize, Serialize};
+
+/// The network-able version of [PerspectiveProjection].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetPerspectiveProjection {
+    /// The vertical field of view (FOV) in radians.
+    pub fov: f32,
+    /// The aspect ratio (width divided by height) of the viewing frustum.
+    ///
+    /// Bevy updates this when the aspect ratio of the window changes, so the receiver's value is
+    /// replaced on its next resize.
+    pub aspect_ratio: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl From<PerspectiveProjection> for NetPerspectiveProjection {
+    fn from(o: PerspectiveProjection) -> Self {
+        NetPerspectiveProjection {
+            fov: o.fov,
+            aspect_ratio: o.aspect_ratio,
+            near: o.near,
+            far: o.far,
+        }
+    }
+}
+
+impl From<NetPerspectiveProjection> for PerspectiveProjection {
+    fn from(o: NetPerspectiveProjection) -> Self {
+        PerspectiveProjection {
+            fov: o.fov,
+            aspect_ratio: o.aspect_ratio,
+            near: o.near,
+            far: o.far,
+        }
+    }
+}
+
+/// The network-able version of [Projection].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NetProjection {
+    Perspective(NetPerspectiveProjection),
+    Orthographic(NetOrthographicProjection),
+}
+
+impl From<Projection> for NetProjection {
+    fn from(o: Projection) -> Self {
+        match o {
+            Projection::Perspective(p) => NetProjection::Perspective(p.into()),
+            Projection::Orthographic(p) => NetProjection::Orthographic(p.into()),
+        }
+    }
+}
+
+impl From<NetProjection> for Projection {
+    fn from(o: NetProjection) -> Self {
+        match o {
+            NetProjection::Perspective(p) => Projection::Perspective(p.into()),
+            NetProjection::Orthographic(p) => Projection::Orthographic(p.into()),
+        }
+    }
+}
+
+/// The network-able version of [Viewport].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetViewport {
+    /// The physical position to render this viewport to within the render target.
+    /// (0,0) corresponds to the top-left corner
+    pub physical_position: UVec2,
+    /// The physical size of the viewport rectangle to render to within the render target.
+    pub physical_size: UVec2,
+    /// The minimum and maximum depth to render (on a scale from 0.0 to 1.0).
+    pub depth: (f32, f32),
+}
+
+impl From<Viewport> for NetViewport {
+    fn from(o: Viewport) -> Self {
+        NetViewport {
+            physical_position: o.physical_position,
+            physical_size: o.physical_size,
+            depth: (o.depth.start, o.depth.end),
+        }
+    }
+}
+
+impl From<NetViewport> for Viewport {
+    fn from(o: NetViewport) -> Self {
+        Viewport {
+            physical_position: o.physical_position,
+            physical_size: o.physical_size,
+            depth: o.depth.0..o.depth.1,
+        }
+    }
+}
+
+/// The network-able version of [Camera].
+///
+/// The render target is not included, since it refers to a local window or image. Converting
+/// this into a [Camera] uses the default target (the primary window); the computed values are
+/// refreshed by bevy's camera system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetCamera {
+    /// If set, this camera will render to the given [`Viewport`] rectangle within its render
+    /// target.
+    pub viewport: Option<NetViewport>,
+    /// Cameras with a lower priority will be rendered before cameras with a higher priority.
+    pub priority: isize,
+    /// If this is set to `true`, this camera will be rendered. If `false`, this camera will not be
+    /// rendered.
+    pub is_active: bool,
+    /// If this is set to `true`, the camera will use an intermediate "high dynamic range" render
+    /// texture.
+    pub hdr: bool,
+}
+
+impl From<Camera> for NetCamera {
+    fn from(o: Camera) -> Self {
+        NetCamera {
+            viewport: o.viewport.map(Into::into),
+            priority: o.priority,
+            is_active: o.is_active,
+            hdr: o.hdr,
+        }
+    }
+}
+
+impl From<NetCamera> for Camera {
+    fn from(o: NetCamera) -> Self {
+        Camera {
+            viewport: o.viewport.map(Into::into),
+            priority: o.priority,
+            is_active: o.is_active,
+            hdr: o.hdr,
+            ..default()
+        }
+    }
+}
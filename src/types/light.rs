@@ -2,6 +2,8 @@
 //!  - [AmbientLight]
 //!  - [DirectionalLight]
 //!  - [PointLight]
+//!  - [SpotLight]
+//!  - [ClearColor]
 
 use crate::types::misc::NetOrthographicProjection;
 use bevy::prelude::*;
@@ -115,3 +117,74 @@ impl From<NetPointLight> for PointLight {
         }
     }
 }
+
+/// The network-able version of [SpotLight].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetSpotLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub radius: f32,
+    pub shadows_enabled: bool,
+    pub shadow_depth_bias: f32,
+    /// A bias applied along the direction of the fragment's surface normal. It is scaled to the
+    /// shadow map's texel size so that it can be small close to the camera and gets larger further
+    /// away.
+    pub shadow_normal_bias: f32,
+    /// Angle defining the distance from the spot light direction to the outer limit
+    /// of the light's cone of effect.
+    pub outer_angle: f32,
+    /// Angle defining the distance from the spot light direction to the inner limit
+    /// of the light's cone of effect.
+    pub inner_angle: f32,
+}
+
+impl From<SpotLight> for NetSpotLight {
+    fn from(o: SpotLight) -> Self {
+        NetSpotLight {
+            color: o.color,
+            intensity: o.intensity,
+            range: o.range,
+            radius: o.radius,
+            shadows_enabled: o.shadows_enabled,
+            shadow_depth_bias: o.shadow_depth_bias,
+            shadow_normal_bias: o.shadow_normal_bias,
+            outer_angle: o.outer_angle,
+            inner_angle: o.inner_angle,
+        }
+    }
+}
+
+impl From<NetSpotLight> for SpotLight {
+    fn from(o: NetSpotLight) -> Self {
+        SpotLight {
+            color: o.color,
+            intensity: o.intensity,
+            range: o.range,
+            radius: o.radius,
+            shadows_enabled: o.shadows_enabled,
+            shadow_depth_bias: o.shadow_depth_bias,
+            shadow_normal_bias: o.shadow_normal_bias,
+            outer_angle: o.outer_angle,
+            inner_angle: o.inner_angle,
+        }
+    }
+}
+
+/// The network-able version of [ClearColor].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetClearColor {
+    pub color: Color,
+}
+
+impl From<ClearColor> for NetClearColor {
+    fn from(o: ClearColor) -> Self {
+        NetClearColor { color: o.0 }
+    }
+}
+
+impl From<NetClearColor> for ClearColor {
+    fn from(o: NetClearColor) -> Self {
+        ClearColor(o.color)
+    }
+}
@@ -0,0 +1,169 @@
+//! Types in this file:
+//! - [Color]
+//! - [StandardMaterial]
+
+use crate::types::NetAlphaMode;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A compact network-able version of [Color].
+///
+/// [Color] is sent as 4 or more `f32`s and an enum tag. This is 4 bytes in [`NetColor::Srgb`],
+/// or 8 bytes in [`NetColor::Linear`], which also keeps values above `1.0`, like for emissive
+/// colors.
+///
+/// Converting from a [Color] uses [`NetColor::Srgb`]. Use [`NetColor::linear`] for the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NetColor {
+    /// Red, green, blue and alpha in sRGB space, as `u8`s.
+    ///
+    /// Values are clamped to `0.0..=1.0`.
+    Srgb([u8; 4]),
+    /// Red, green, blue and alpha in linear space, as half-precision floats stored as their bits.
+    Linear([u16; 4]),
+}
+
+impl NetColor {
+    /// Creates a [`NetColor::Srgb`] from `color`.
+    pub fn srgb(color: Color) -> Self {
+        NetColor::Srgb(
+            color
+                .as_rgba_f32()
+                .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8),
+        )
+    }
+
+    /// Creates a [`NetColor::Linear`] from `color`.
+    pub fn linear(color: Color) -> Self {
+        NetColor::Linear(color.as_linear_rgba_f32().map(f32_to_f16))
+    }
+}
+
+impl From<Color> for NetColor {
+    fn from(o: Color) -> Self {
+        NetColor::srgb(o)
+    }
+}
+
+impl From<NetColor> for Color {
+    fn from(o: NetColor) -> Self {
+        match o {
+            NetColor::Srgb([r, g, b, a]) => Color::rgba_u8(r, g, b, a),
+            NetColor::Linear(rgba) => {
+                let [r, g, b, a] = rgba.map(f16_to_f32);
+                Color::rgba_linear(r, g, b, a)
+            }
+        }
+    }
+}
+
+/// Converts `value` to the bits of a half-precision float, rounding to nearest even.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x7f_ffff;
+
+    // Infinity and NaN.
+    if exp == 0xff {
+        let nan = if man != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        // Too large, so it becomes infinity.
+        return sign | 0x7c00;
+    }
+    if exp <= 0 {
+        // Too small for a normal half, so it becomes subnormal, or zero.
+        if exp < -10 {
+            return sign;
+        }
+        let man = man | 0x80_0000;
+        let shift = (14 - exp) as u32;
+        let half = man >> shift;
+        let rem = man & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round = rem > halfway || (rem == halfway && half & 1 == 1);
+        return sign | (half + round as u32) as u16;
+    }
+
+    // A carry out of the mantissa correctly moves to the next exponent, or to infinity.
+    let half = ((exp as u32) << 10) | (man >> 13);
+    let rem = man & 0x1fff;
+    let round = rem > 0x1000 || (rem == 0x1000 && half & 1 == 1);
+    sign | (half + round as u32) as u16
+}
+
+/// Converts the bits of a half-precision float to an `f32`. This is exact.
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exp = ((half >> 10) & 0x1f) as u32;
+    let man = (half & 0x3ff) as u32;
+
+    let bits = match exp {
+        0 if man == 0 => sign,
+        0 => {
+            // Subnormal, which is a normal `f32`.
+            let value = man as f32 * 2f32.powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        0x1f => sign | 0x7f80_0000 | (man << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (man << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// The network-able version of the parameters of a [StandardMaterial].
+///
+/// Materials are assets, so this isn't a component. It holds the colors and scalar parameters,
+/// but not the textures. Sync it with [`sync_materials`](crate::AppExt::sync_materials), which
+/// applies it to the material of each entity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetMaterial {
+    /// Sent as [`NetColor::Srgb`].
+    pub base_color: NetColor,
+    /// Sent as [`NetColor::Linear`], since emissive colors are often brighter than `1.0`.
+    pub emissive: NetColor,
+    pub perceptual_roughness: f32,
+    pub metallic: f32,
+    pub reflectance: f32,
+    pub alpha_mode: NetAlphaMode,
+    pub unlit: bool,
+}
+
+impl NetMaterial {
+    /// Applies these parameters to `material`, leaving its textures and other fields as they are.
+    pub fn apply(&self, material: &mut StandardMaterial) {
+        material.base_color = self.base_color.into();
+        material.emissive = self.emissive.into();
+        material.perceptual_roughness = self.perceptual_roughness;
+        material.metallic = self.metallic;
+        material.reflectance = self.reflectance;
+        material.alpha_mode = self.alpha_mode.into();
+        material.unlit = self.unlit;
+    }
+}
+
+impl From<&StandardMaterial> for NetMaterial {
+    fn from(o: &StandardMaterial) -> Self {
+        NetMaterial {
+            base_color: NetColor::srgb(o.base_color),
+            emissive: NetColor::linear(o.emissive),
+            perceptual_roughness: o.perceptual_roughness,
+            metallic: o.metallic,
+            reflectance: o.reflectance,
+            alpha_mode: o.alpha_mode.into(),
+            unlit: o.unlit,
+        }
+    }
+}
+
+impl From<NetMaterial> for StandardMaterial {
+    fn from(o: NetMaterial) -> Self {
+        let mut material = StandardMaterial::default();
+        o.apply(&mut material);
+        material
+    }
+}
@@ -2,22 +2,60 @@
 //!
 //! Types:
 //!  - [Transform]
+//!  - [GlobalTransform]
 //!  - [OrthographicProjection]
+//!  - [PerspectiveProjection]
+//!  - [Projection]
+//!  - [Camera]
+//!  - [Viewport](bevy::render::camera::Viewport)
 //!  - [AmbientLight]
 //!  - [DirectionalLight]
 //!  - [PointLight]
+//!  - [SpotLight]
+//!  - [ClearColor]
 //!  - [Name]
 //!  - [Visibility]
 //!  - [AlphaMode]
 //!  - [EulerRot]
+//!  - [Sprite]
+//!  - [TextureAtlasSprite]
+//!  - [Anchor](bevy::sprite::Anchor)
+//!  - [Handle]
+//!  - [Text]
+//!  - [Style]
+//!  - [BackgroundColor]
+//!  - [Color]
+//!  - [StandardMaterial]
+//!  - [AnimationPlayer] (with the `animation` feature)
+//!  - `Velocity` from `bevy_rapier3d` (with the `rapier` feature)
 //!
 //! If you think other network-able types would be helpful to many users, and think it should be
 //! included here, please send a PR.
 
+#[cfg(feature = "animation")]
+mod animation;
+mod asset;
+mod camera;
 mod light;
+mod material;
 mod misc;
+#[cfg(feature = "rapier")]
+mod physics;
+mod sprite;
+mod text;
 mod transform;
+mod ui;
 
+#[cfg(feature = "animation")]
+pub use animation::*;
+pub use asset::*;
+pub use camera::*;
 pub use light::*;
+pub use material::*;
 pub use misc::*;
+#[cfg(feature = "rapier")]
+pub use physics::*;
+pub use sprite::*;
+pub use text::*;
 pub use transform::*;
+pub use ui::*;
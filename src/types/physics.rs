@@ -0,0 +1,33 @@
+//! Types in this file:
+//! - [Velocity]
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+/// The network-able version of rapier's [Velocity].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetVelocity {
+    /// The linear velocity of the rigid-body.
+    pub linvel: Vec3,
+    /// The angular velocity of the rigid-body.
+    pub angvel: Vec3,
+}
+
+impl From<Velocity> for NetVelocity {
+    fn from(o: Velocity) -> Self {
+        NetVelocity {
+            linvel: o.linvel,
+            angvel: o.angvel,
+        }
+    }
+}
+
+impl From<NetVelocity> for Velocity {
+    fn from(o: NetVelocity) -> Self {
+        Velocity {
+            linvel: o.linvel,
+            angvel: o.angvel,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Types in this file:
+//! - [Sprite]
+//! - [TextureAtlasSprite]
+//! - [Anchor]
+
+use bevy::math::Rect;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use serde::{Deserialize, Serialize};
+
+/// The network-able version of [Sprite].
+///
+/// This does not include the image, which is a separate `Handle<Image>` component.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetSprite {
+    /// The sprite's color tint
+    pub color: Color,
+    /// Flip the sprite along the `X` axis
+    pub flip_x: bool,
+    /// Flip the sprite along the `Y` axis
+    pub flip_y: bool,
+    /// An optional custom size for the sprite that will be used when rendering, instead of the size
+    /// of the sprite's image
+    pub custom_size: Option<Vec2>,
+    /// An optional rectangle of the sprite's image to render, as its `min` and `max` corners.
+    pub rect: Option<(Vec2, Vec2)>,
+    /// [`Anchor`] point of the sprite in the world
+    pub anchor: NetAnchor,
+}
+
+impl From<Sprite> for NetSprite {
+    fn from(o: Sprite) -> Self {
+        NetSprite {
+            color: o.color,
+            flip_x: o.flip_x,
+            flip_y: o.flip_y,
+            custom_size: o.custom_size,
+            rect: o.rect.map(|rect| (rect.min, rect.max)),
+            anchor: o.anchor.into(),
+        }
+    }
+}
+
+impl From<NetSprite> for Sprite {
+    fn from(o: NetSprite) -> Self {
+        Sprite {
+            color: o.color,
+            flip_x: o.flip_x,
+            flip_y: o.flip_y,
+            custom_size: o.custom_size,
+            rect: o.rect.map(|(min, max)| Rect { min, max }),
+            anchor: o.anchor.into(),
+        }
+    }
+}
+
+/// The network-able version of [TextureAtlasSprite].
+///
+/// This does not include the texture atlas, which is a separate `Handle<TextureAtlas>` component.
+/// Syncing this syncs the animation frame, through `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetTextureAtlasSprite {
+    pub color: Color,
+    /// The index of the sprite in the texture atlas.
+    pub index: usize,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// An optional custom size for the sprite that will be used when rendering, instead of the size
+    /// of the sprite's image in the atlas
+    pub custom_size: Option<Vec2>,
+    pub anchor: NetAnchor,
+}
+
+impl From<TextureAtlasSprite> for NetTextureAtlasSprite {
+    fn from(o: TextureAtlasSprite) -> Self {
+        NetTextureAtlasSprite {
+            color: o.color,
+            index: o.index,
+            flip_x: o.flip_x,
+            flip_y: o.flip_y,
+            custom_size: o.custom_size,
+            anchor: o.anchor.into(),
+        }
+    }
+}
+
+impl From<NetTextureAtlasSprite> for TextureAtlasSprite {
+    fn from(o: NetTextureAtlasSprite) -> Self {
+        TextureAtlasSprite {
+            color: o.color,
+            index: o.index,
+            flip_x: o.flip_x,
+            flip_y: o.flip_y,
+            custom_size: o.custom_size,
+            anchor: o.anchor.into(),
+        }
+    }
+}
+
+/// The network-able version of [Anchor].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum NetAnchor {
+    #[default]
+    Center,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+    CenterLeft,
+    CenterRight,
+    TopLeft,
+    TopCenter,
+    TopRight,
+    /// Custom anchor point. Top left is `(-0.5, 0.5)`, center is `(0.0, 0.0)`. The value will
+    /// be scaled with the sprite size.
+    Custom(Vec2),
+}
+
+impl From<Anchor> for NetAnchor {
+    fn from(o: Anchor) -> Self {
+        match o {
+            Anchor::Center => NetAnchor::Center,
+            Anchor::BottomLeft => NetAnchor::BottomLeft,
+            Anchor::BottomCenter => NetAnchor::BottomCenter,
+            Anchor::BottomRight => NetAnchor::BottomRight,
+            Anchor::CenterLeft => NetAnchor::CenterLeft,
+            Anchor::CenterRight => NetAnchor::CenterRight,
+            Anchor::TopLeft => NetAnchor::TopLeft,
+            Anchor::TopCenter => NetAnchor::TopCenter,
+            Anchor::TopRight => NetAnchor::TopRight,
+            Anchor::Custom(v) => NetAnchor::Custom(v),
+        }
+    }
+}
+
+impl From<NetAnchor> for Anchor {
+    fn from(o: NetAnchor) -> Self {
+        match o {
+            NetAnchor::Center => Anchor::Center,
+            NetAnchor::BottomLeft => Anchor::BottomLeft,
+            NetAnchor::BottomCenter => Anchor::BottomCenter,
+            NetAnchor::BottomRight => Anchor::BottomRight,
+            NetAnchor::CenterLeft => Anchor::CenterLeft,
+            NetAnchor::CenterRight => Anchor::CenterRight,
+            NetAnchor::TopLeft => Anchor::TopLeft,
+            NetAnchor::TopCenter => Anchor::TopCenter,
+            NetAnchor::TopRight => Anchor::TopRight,
+            NetAnchor::Custom(v) => Anchor::Custom(v),
+        }
+    }
+}
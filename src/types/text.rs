@@ -0,0 +1,79 @@
+//! Types in this file:
+//! - [Text]
+//! - [TextSection]
+
+use crate::asset::{NetAssetConv, NetAssetKeys};
+use crate::types::NetHandle;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The network-able version of [TextSection].
+///
+/// The font is sent as a [`NetHandle`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetTextSection {
+    pub value: String,
+    pub font: NetHandle<Font>,
+    pub font_size: f32,
+    pub color: Color,
+}
+
+/// The network-able version of [Text].
+///
+/// Since the fonts are handles, this is converted with [`NetAssetConv`] instead of `From`. Sync
+/// it with [`sync_comp_assets`](crate::AppExt::sync_comp_assets):
+/// ```ignore
+/// app.sync_comp_assets::<Text, NetText>(&mut table, Transport::TCP);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetText {
+    pub sections: Vec<NetTextSection>,
+    pub vertical: VerticalAlign,
+    pub horizontal: HorizontalAlign,
+}
+
+impl NetAssetConv<Text> for NetText {
+    fn from_comp(comp: &Text, assets: Option<&AssetServer>, keys: &NetAssetKeys) -> Option<Self> {
+        let sections = comp
+            .sections
+            .iter()
+            .map(|section| {
+                Some(NetTextSection {
+                    value: section.value.clone(),
+                    font: NetHandle::from_handle(&section.style.font, assets, keys)?,
+                    font_size: section.style.font_size,
+                    color: section.style.color,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(NetText {
+            sections,
+            vertical: comp.alignment.vertical,
+            horizontal: comp.alignment.horizontal,
+        })
+    }
+
+    fn into_comp(self, assets: Option<&AssetServer>, keys: &NetAssetKeys) -> Option<Text> {
+        let sections = self
+            .sections
+            .into_iter()
+            .map(|section| {
+                Some(TextSection {
+                    value: section.value,
+                    style: TextStyle {
+                        font: section.font.to_handle(assets, keys)?,
+                        font_size: section.font_size,
+                        color: section.color,
+                    },
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Text {
+            sections,
+            alignment: TextAlignment {
+                vertical: self.vertical,
+                horizontal: self.horizontal,
+            },
+        })
+    }
+}
@@ -7,6 +7,7 @@
 //! - [NetTransform2d]
 //! - [NetTransform2dTR]
 //! - [NetTransform2dT]
+//! - [NetWorldTransform]
 
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
@@ -248,3 +249,62 @@ impl From<NetTransform2dT> for Transform {
         }
     }
 }
+
+/// The network-able version of [GlobalTransform].
+///
+/// Contains the transform in world space, so the receiver can place the entity correctly even if
+/// it has a different parent there. Sync it with
+/// [`sync_world_transform`](crate::AppExt::sync_world_transform), which writes the received value
+/// to the [Transform], relative to the receiver's parent.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetWorldTransform {
+    /// Position of the entity in world space.
+    pub translation: Vec3,
+    /// Rotation of the entity in world space.
+    pub rotation: Quat,
+    /// Scale of the entity in world space.
+    pub scale: Vec3,
+}
+
+impl NetWorldTransform {
+    /// Solves for the local [Transform] that places the entity at this world transform, when its
+    /// parent is at `parent`.
+    ///
+    /// If there is no parent, this is the world transform itself.
+    pub fn to_local(&self, parent: Option<&GlobalTransform>) -> Transform {
+        let world = Transform::from(*self);
+        match parent {
+            Some(parent) => {
+                Transform::from_matrix(parent.compute_matrix().inverse() * world.compute_matrix())
+            }
+            None => world,
+        }
+    }
+}
+
+impl From<GlobalTransform> for NetWorldTransform {
+    fn from(o: GlobalTransform) -> Self {
+        let (scale, rotation, translation) = o.to_scale_rotation_translation();
+        NetWorldTransform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+}
+
+impl From<NetWorldTransform> for Transform {
+    fn from(o: NetWorldTransform) -> Self {
+        Transform {
+            translation: o.translation,
+            rotation: o.rotation,
+            scale: o.scale,
+        }
+    }
+}
+
+impl From<NetWorldTransform> for GlobalTransform {
+    fn from(o: NetWorldTransform) -> Self {
+        Transform::from(o).into()
+    }
+}
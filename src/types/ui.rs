@@ -0,0 +1,164 @@
+//! Types in this file:
+//! - [Style]
+//! - [UiRect]
+//! - [Size]
+//! - [BackgroundColor]
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The network-able version of [UiRect].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetUiRect {
+    pub left: Val,
+    pub right: Val,
+    pub top: Val,
+    pub bottom: Val,
+}
+
+impl From<UiRect> for NetUiRect {
+    fn from(o: UiRect) -> Self {
+        NetUiRect {
+            left: o.left,
+            right: o.right,
+            top: o.top,
+            bottom: o.bottom,
+        }
+    }
+}
+
+impl From<NetUiRect> for UiRect {
+    fn from(o: NetUiRect) -> Self {
+        UiRect {
+            left: o.left,
+            right: o.right,
+            top: o.top,
+            bottom: o.bottom,
+        }
+    }
+}
+
+/// The network-able version of [Size].
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetSize {
+    pub width: Val,
+    pub height: Val,
+}
+
+impl From<Size> for NetSize {
+    fn from(o: Size) -> Self {
+        NetSize {
+            width: o.width,
+            height: o.height,
+        }
+    }
+}
+
+impl From<NetSize> for Size {
+    fn from(o: NetSize) -> Self {
+        Size {
+            width: o.width,
+            height: o.height,
+        }
+    }
+}
+
+/// The network-able version of the UI [Style].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetUiStyle {
+    pub display: Display,
+    pub position_type: PositionType,
+    pub direction: Direction,
+    pub flex_direction: FlexDirection,
+    pub flex_wrap: FlexWrap,
+    pub align_items: AlignItems,
+    pub align_self: AlignSelf,
+    pub align_content: AlignContent,
+    pub justify_content: JustifyContent,
+    pub position: NetUiRect,
+    pub margin: NetUiRect,
+    pub padding: NetUiRect,
+    pub border: NetUiRect,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    pub flex_basis: Val,
+    pub size: NetSize,
+    pub min_size: NetSize,
+    pub max_size: NetSize,
+    pub aspect_ratio: Option<f32>,
+    pub overflow: Overflow,
+}
+
+impl From<Style> for NetUiStyle {
+    fn from(o: Style) -> Self {
+        NetUiStyle {
+            display: o.display,
+            position_type: o.position_type,
+            direction: o.direction,
+            flex_direction: o.flex_direction,
+            flex_wrap: o.flex_wrap,
+            align_items: o.align_items,
+            align_self: o.align_self,
+            align_content: o.align_content,
+            justify_content: o.justify_content,
+            position: o.position.into(),
+            margin: o.margin.into(),
+            padding: o.padding.into(),
+            border: o.border.into(),
+            flex_grow: o.flex_grow,
+            flex_shrink: o.flex_shrink,
+            flex_basis: o.flex_basis,
+            size: o.size.into(),
+            min_size: o.min_size.into(),
+            max_size: o.max_size.into(),
+            aspect_ratio: o.aspect_ratio,
+            overflow: o.overflow,
+        }
+    }
+}
+
+impl From<NetUiStyle> for Style {
+    fn from(o: NetUiStyle) -> Self {
+        Style {
+            display: o.display,
+            position_type: o.position_type,
+            direction: o.direction,
+            flex_direction: o.flex_direction,
+            flex_wrap: o.flex_wrap,
+            align_items: o.align_items,
+            align_self: o.align_self,
+            align_content: o.align_content,
+            justify_content: o.justify_content,
+            position: o.position.into(),
+            margin: o.margin.into(),
+            padding: o.padding.into(),
+            border: o.border.into(),
+            flex_grow: o.flex_grow,
+            flex_shrink: o.flex_shrink,
+            flex_basis: o.flex_basis,
+            size: o.size.into(),
+            min_size: o.min_size.into(),
+            max_size: o.max_size.into(),
+            aspect_ratio: o.aspect_ratio,
+            overflow: o.overflow,
+        }
+    }
+}
+
+/// The network-able version of [BackgroundColor].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetBackgroundColor {
+    pub color: Color,
+}
+
+impl From<BackgroundColor> for NetBackgroundColor {
+    fn from(o: BackgroundColor) -> Self {
+        NetBackgroundColor { color: o.0 }
+    }
+}
+
+impl From<NetBackgroundColor> for BackgroundColor {
+    fn from(o: NetBackgroundColor) -> Self {
+        BackgroundColor(o.color)
+    }
+}
@@ -0,0 +1,75 @@
+//! Syncing transforms in world space.
+//!
+//! [`NetTransform`](crate::types::NetTransform) syncs the [`Transform`], which is relative to the
+//! parent, so an entity with a different parent on the receiver ends up in the wrong place. With
+//! [`sync_world_transform`](crate::AppExt::sync_world_transform), the [`GlobalTransform`] is sent
+//! instead, and the receiver solves for the [`Transform`] relative to its own parent.
+//!
+//! Entities synced this way use a `NetComp<GlobalTransform, NetWorldTransform>`, in place of a
+//! `NetComp<Transform, NetTransform>`.
+
+use crate::app::get_latest_msg;
+use crate::spectator::{is_spectator, Spectators};
+use crate::sync::{CNetDir, NetComp, NetCompMsg, NetEntity};
+use crate::types::NetWorldTransform;
+use bevy::prelude::*;
+use carrier_pigeon::net::{CIdSpec, NetMsg};
+use carrier_pigeon::{Client, Server};
+
+/// A system that receives world transforms and writes them to the [`Transform`]s, relative to the
+/// current parent of each entity.
+///
+/// The parent's [`GlobalTransform`] is the one from the last transform propagation, so a parent
+/// that moved this frame makes the child lag a frame behind.
+///
+/// Most of the time, you will call [`sync_world_transform`](crate::AppExt::sync_world_transform)
+/// which will add this system.
+#[allow(clippy::type_complexity)]
+pub fn world_transform_recv(
+    server: Option<Res<Server>>,
+    client: Option<Res<Client>>,
+    spectators: Option<Res<Spectators>>,
+    mut q: Query<(
+        &NetEntity,
+        &mut NetComp<GlobalTransform, NetWorldTransform>,
+        &mut Transform,
+        Option<&Parent>,
+    )>,
+    parents: Query<&GlobalTransform>,
+) {
+    let (msgs, is_server): (Vec<NetMsg<NetCompMsg<NetWorldTransform>>>, bool) =
+        if let Some(server) = &server {
+            // Ignore the messages from spectators.
+            let msgs = server
+                .recv::<NetCompMsg<NetWorldTransform>>()
+                .filter(|msg| !is_spectator(spectators.as_deref(), msg.cid))
+                .collect();
+            (msgs, true)
+        } else if let Some(client) = &client {
+            (
+                client.recv::<NetCompMsg<NetWorldTransform>>().collect(),
+                false,
+            )
+        } else {
+            return;
+        };
+
+    for (net_e, mut net_c, mut transform, parent) in q.iter_mut() {
+        let spec = if is_server {
+            match net_c.s_dir.from() {
+                Some(spec) => *spec,
+                None => continue,
+            }
+        } else if net_c.c_dir == CNetDir::From {
+            CIdSpec::All
+        } else {
+            continue;
+        };
+
+        if let Some(valid_msg) = get_latest_msg(&msgs, net_c.last, spec, net_e.id) {
+            net_c.last = valid_msg.time;
+            let parent = parent.and_then(|parent| parents.get(parent.get()).ok());
+            *transform = valid_msg.msg.to_local(parent);
+        }
+    }
+}
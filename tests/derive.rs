@@ -0,0 +1,137 @@
+//! Round-trip tests for the `NetMessage` derive.
+
+#![cfg(feature = "derive")]
+
+use bevy::prelude::*;
+use bevy_pigeon::NetMessage;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Serializes and deserializes `msg`, as it would be sent over the network.
+fn through_wire<M: Serialize + DeserializeOwned>(msg: &M) -> M {
+    let data = bincode::serialize(msg).expect("failed to serialize");
+    bincode::deserialize(&data).expect("failed to deserialize")
+}
+
+#[derive(Component, Clone, Debug, PartialEq, NetMessage)]
+struct Plain {
+    id: u32,
+    name: String,
+    alive: bool,
+}
+
+#[test]
+fn plain() {
+    let plain = Plain {
+        id: 7,
+        name: "pigeon".to_owned(),
+        alive: true,
+    };
+    let net = NetPlain::from(plain.clone());
+    assert_eq!(through_wire(&net), net);
+    assert_eq!(Plain::from(net), plain);
+}
+
+#[derive(Component, Clone, Debug, PartialEq, NetMessage)]
+struct Skipped {
+    id: u32,
+    #[net(skip)]
+    cache: Vec<u8>,
+}
+
+#[test]
+fn skip() {
+    let skipped = Skipped {
+        id: 3,
+        cache: vec![1, 2, 3],
+    };
+    let net = NetSkipped::from(skipped);
+    assert_eq!(net, NetSkipped { id: 3 });
+    assert_eq!(through_wire(&net), net);
+    // Skipped fields are set to their default.
+    assert_eq!(
+        Skipped::from(net),
+        Skipped {
+            id: 3,
+            cache: vec![],
+        }
+    );
+}
+
+#[derive(Component, Clone, Debug, PartialEq, NetMessage)]
+struct Quantized {
+    #[net(quantize = 0.1)]
+    x: f32,
+    #[net(quantize = 0.5f32)]
+    y: f32,
+    #[net(quantize = 0.01f64)]
+    z: f64,
+}
+
+#[test]
+fn quantize() {
+    let net = NetQuantized::from(Quantized {
+        x: 1.23,
+        y: -2.6,
+        z: 0.126,
+    });
+    assert_eq!(
+        net,
+        NetQuantized {
+            x: 12,
+            y: -5,
+            z: 13
+        }
+    );
+    assert_eq!(through_wire(&net), net);
+
+    let back = Quantized::from(net);
+    assert!((back.x - 1.2).abs() < 1e-6);
+    assert!((back.y + 2.5).abs() < 1e-6);
+    assert!((back.z - 0.13).abs() < 1e-9);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Secs(f32);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Millis(u32);
+
+impl From<Secs> for Millis {
+    fn from(o: Secs) -> Self {
+        Millis((o.0 * 1000.0).round() as u32)
+    }
+}
+
+impl From<Millis> for Secs {
+    fn from(o: Millis) -> Self {
+        Secs(o.0 as f32 / 1000.0)
+    }
+}
+
+#[derive(Component, Clone, Debug, PartialEq, NetMessage)]
+struct Cooldown {
+    #[net(with = "Millis")]
+    left: Secs,
+}
+
+#[test]
+fn with() {
+    let cooldown = Cooldown { left: Secs(1.5) };
+    let net = NetCooldown::from(cooldown.clone());
+    assert_eq!(net, NetCooldown { left: Millis(1500) });
+    assert_eq!(through_wire(&net), net);
+    assert_eq!(Cooldown::from(net), cooldown);
+}
+
+#[derive(Component, Clone, Debug, PartialEq, NetMessage)]
+#[net(name = "PairMsg")]
+struct Pair(u32, #[net(skip)] u8, u16);
+
+#[test]
+fn tuple_and_name() {
+    let net = PairMsg::from(Pair(1, 2, 3));
+    assert_eq!(net, PairMsg(1, 3));
+    assert_eq!(through_wire(&net), net);
+    assert_eq!(Pair::from(net), Pair(1, 0, 3));
+}
@@ -0,0 +1,383 @@
+//! Round-trip tests for the network-able types.
+
+#![cfg(feature = "types")]
+
+use bevy::asset::{FileAssetIo, HandleId};
+use bevy::math::Rect;
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::sprite::Anchor;
+use bevy::tasks::{IoTaskPool, TaskPool};
+use bevy_pigeon::types::*;
+use bevy_pigeon::NetAssetKeys;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Serializes and deserializes `msg`, as it would be sent over the network.
+fn through_wire<M: Serialize + DeserializeOwned>(msg: &M) -> M {
+    let data = bincode::serialize(msg).expect("failed to serialize");
+    bincode::deserialize(&data).expect("failed to deserialize")
+}
+
+/// Checks that `msg` survives being converted to `T` and back, and being sent.
+fn round_trip<T, M>(msg: M)
+where
+    T: From<M>,
+    M: From<T> + Serialize + DeserializeOwned + PartialEq + Clone + Debug,
+{
+    assert_eq!(M::from(T::from(msg.clone())), msg);
+    assert_eq!(through_wire(&msg), msg);
+}
+
+#[test]
+fn sprite() {
+    let sprite = Sprite {
+        color: Color::rgba(0.1, 0.2, 0.3, 0.4),
+        flip_x: true,
+        flip_y: false,
+        custom_size: Some(Vec2::new(16.0, 32.0)),
+        rect: Some(Rect::new(0.0, 0.0, 8.0, 8.0)),
+        anchor: Anchor::Custom(Vec2::new(0.25, -0.5)),
+    };
+    let net = NetSprite::from(sprite);
+    assert_eq!(net.anchor, NetAnchor::Custom(Vec2::new(0.25, -0.5)));
+    assert_eq!(net.rect, Some((Vec2::ZERO, Vec2::splat(8.0))));
+    round_trip::<Sprite, _>(net);
+    round_trip::<Sprite, _>(NetSprite::from(Sprite::default()));
+}
+
+#[test]
+fn texture_atlas_sprite() {
+    let sprite = TextureAtlasSprite {
+        color: Color::RED,
+        index: 7,
+        flip_x: false,
+        flip_y: true,
+        custom_size: None,
+        anchor: Anchor::BottomLeft,
+    };
+    let net = NetTextureAtlasSprite::from(sprite);
+    assert_eq!(net.index, 7);
+    assert_eq!(net.anchor, NetAnchor::BottomLeft);
+    round_trip::<TextureAtlasSprite, _>(net);
+}
+
+#[test]
+fn anchor() {
+    let anchors = [
+        NetAnchor::Center,
+        NetAnchor::BottomLeft,
+        NetAnchor::BottomCenter,
+        NetAnchor::BottomRight,
+        NetAnchor::CenterLeft,
+        NetAnchor::CenterRight,
+        NetAnchor::TopLeft,
+        NetAnchor::TopCenter,
+        NetAnchor::TopRight,
+        NetAnchor::Custom(Vec2::new(-0.1, 0.4)),
+    ];
+    for anchor in anchors {
+        round_trip::<Anchor, _>(anchor);
+    }
+}
+
+#[test]
+fn handle() {
+    let by_path = NetHandle::<Image>::new(NetAssetRef::Path("skins/red.png".into()));
+    assert_eq!(through_wire(&by_path), by_path);
+    let by_key = NetHandle::<Mesh>::new(NetAssetRef::Key("sword".into()));
+    assert_eq!(through_wire(&by_key), by_key);
+}
+
+#[test]
+fn handle_by_key() {
+    let handle = Handle::<Image>::weak(HandleId::random::<Image>());
+    let mut keys = NetAssetKeys::default();
+    keys.insert("skin", &handle);
+
+    // Keys work without an `AssetServer`.
+    let net = NetHandle::from_handle(&handle, None, &keys).expect("the handle has a key");
+    assert_eq!(net.asset, NetAssetRef::Key("skin".into()));
+    assert_eq!(
+        through_wire(&net).to_handle(None, &keys),
+        Some(handle.clone())
+    );
+
+    // Re-keying the handle removes the old key.
+    keys.insert("red_skin", &handle);
+    assert!(keys.get("skin").is_none());
+    assert_eq!(keys.key(&handle), Some("red_skin"));
+    let old = NetHandle::<Image>::new(NetAssetRef::Key("skin".into()));
+    assert_eq!(old.to_handle(None, &keys), None);
+
+    assert_eq!(
+        NetHandle::from_handle(&handle, None, &NetAssetKeys::default()),
+        None
+    );
+}
+
+#[test]
+fn handle_by_key_wrong_type() {
+    let handle = Handle::<Image>::weak(HandleId::random::<Image>());
+    let mut keys = NetAssetKeys::default();
+    keys.insert("skin", &handle);
+
+    // A key registered for an `Image` is not resolved as a `Mesh`, instead of panicking.
+    let net = NetHandle::<Mesh>::new(NetAssetRef::Key("skin".into()));
+    assert_eq!(net.to_handle(None, &keys), None);
+    assert!(keys.get_typed::<Mesh>("skin").is_none());
+    assert_eq!(keys.get_typed::<Image>("skin"), Some(handle));
+}
+
+#[test]
+fn handle_by_path() {
+    IoTaskPool::init(TaskPool::default);
+    let assets = AssetServer::new(FileAssetIo::new("assets", false));
+    let keys = NetAssetKeys::default();
+    let handle: Handle<Image> = assets.load("skins/red.png#layer");
+
+    let net = NetHandle::from_handle(&handle, Some(&assets), &keys).expect("the handle has a path");
+    assert_eq!(net.asset, NetAssetRef::Path("skins/red.png#layer".into()));
+    assert_eq!(
+        through_wire(&net).to_handle(Some(&assets), &keys),
+        Some(handle.clone())
+    );
+
+    // Paths can't be resolved without an `AssetServer`.
+    assert_eq!(NetHandle::from_handle(&handle, None, &keys), None);
+    assert_eq!(net.to_handle(None, &keys), None);
+}
+
+#[test]
+fn perspective_projection() {
+    let projection = PerspectiveProjection {
+        fov: 1.2,
+        aspect_ratio: 16.0 / 9.0,
+        near: 0.5,
+        far: 500.0,
+    };
+    round_trip::<PerspectiveProjection, _>(NetPerspectiveProjection::from(projection));
+}
+
+#[test]
+fn projection() {
+    let net = NetProjection::from(Projection::Perspective(PerspectiveProjection::default()));
+    assert!(matches!(net, NetProjection::Perspective(_)));
+    let net = NetProjection::from(Projection::Orthographic(OrthographicProjection {
+        scale: 2.0,
+        ..default()
+    }));
+    match through_wire(&net) {
+        NetProjection::Orthographic(p) => assert_eq!(p.scale, 2.0),
+        other => panic!("expected an orthographic projection, got {:?}", other),
+    }
+}
+
+#[test]
+fn camera() {
+    let camera = Camera {
+        viewport: Some(Viewport {
+            physical_position: UVec2::new(10, 20),
+            physical_size: UVec2::new(320, 240),
+            depth: 0.1..0.9,
+        }),
+        priority: -3,
+        is_active: false,
+        hdr: true,
+        ..default()
+    };
+    let net = NetCamera::from(camera);
+    assert_eq!(net.viewport.as_ref().unwrap().depth, (0.1, 0.9));
+    round_trip::<Camera, _>(net);
+}
+
+#[test]
+fn spot_light() {
+    let light = SpotLight {
+        color: Color::ORANGE,
+        intensity: 1200.0,
+        range: 30.0,
+        inner_angle: 0.2,
+        outer_angle: 0.6,
+        shadows_enabled: true,
+        ..default()
+    };
+    round_trip::<SpotLight, _>(NetSpotLight::from(light));
+}
+
+#[test]
+fn clear_color() {
+    round_trip::<ClearColor, _>(NetClearColor::from(ClearColor(Color::MIDNIGHT_BLUE)));
+}
+
+#[test]
+fn ui_style() {
+    let style = Style {
+        display: Display::Flex,
+        position_type: PositionType::Absolute,
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::SpaceBetween,
+        position: UiRect::new(Val::Px(10.0), Val::Auto, Val::Percent(5.0), Val::Undefined),
+        margin: UiRect::all(Val::Px(4.0)),
+        size: Size::new(Val::Px(200.0), Val::Percent(50.0)),
+        aspect_ratio: Some(1.5),
+        overflow: Overflow::Hidden,
+        ..default()
+    };
+    let net = NetUiStyle::from(style);
+    assert_eq!(net.size.width, Val::Px(200.0));
+    round_trip::<Style, _>(net);
+}
+
+#[test]
+fn background_color() {
+    round_trip::<BackgroundColor, _>(NetBackgroundColor::from(BackgroundColor(Color::GRAY)));
+}
+
+#[test]
+fn text() {
+    let text = NetText {
+        sections: vec![
+            NetTextSection {
+                value: "Score: ".into(),
+                font: NetHandle::new(NetAssetRef::Path("fonts/FiraSans-Bold.ttf".into())),
+                font_size: 40.0,
+                color: Color::WHITE,
+            },
+            NetTextSection {
+                value: "42".into(),
+                font: NetHandle::new(NetAssetRef::Key("score-font".into())),
+                font_size: 60.0,
+                color: Color::GOLD,
+            },
+        ],
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Left,
+    };
+    assert_eq!(through_wire(&text), text);
+}
+
+#[cfg(feature = "animation")]
+#[test]
+fn animation_state() {
+    let mut state = NetAnimationState {
+        clip: NetHandle::new(NetAssetRef::Path("models/character.glb#Animation0".into())),
+        elapsed: 1.0,
+        speed: 2.0,
+        paused: false,
+        repeat: true,
+    };
+    assert_eq!(through_wire(&state), state);
+
+    state.advance(0.25);
+    assert_eq!(state.elapsed, 1.5);
+    state.paused = true;
+    state.advance(0.25);
+    assert_eq!(state.elapsed, 1.5);
+}
+
+#[cfg(feature = "audio")]
+#[test]
+fn audio_cue() {
+    use bevy_pigeon::audio::{NetAudioCue, NetAudioFalloff, NetAudioPos};
+
+    let cue = NetAudioCue::new("sounds/shot.ogg")
+        .at(Vec3::new(1.0, 2.0, 3.0))
+        .with_volume(0.5)
+        .with_speed(1.25);
+    assert_eq!(cue.pos, NetAudioPos::Position(Vec3::new(1.0, 2.0, 3.0)));
+    assert_eq!(through_wire(&cue), cue);
+
+    let falloff = NetAudioFalloff {
+        ref_distance: 2.0,
+        max_distance: 10.0,
+    };
+    assert_eq!(falloff.gain(1.0), Some(1.0));
+    assert_eq!(falloff.gain(4.0), Some(0.5));
+    assert_eq!(falloff.gain(11.0), None);
+}
+
+#[cfg(feature = "rapier")]
+#[test]
+fn velocity() {
+    use bevy_rapier3d::prelude::Velocity;
+
+    let velocity = Velocity {
+        linvel: Vec3::new(1.0, -2.0, 3.5),
+        angvel: Vec3::new(0.0, 0.5, 0.0),
+    };
+    round_trip::<Velocity, _>(NetVelocity::from(velocity));
+}
+
+#[test]
+fn world_transform() {
+    let world = GlobalTransform::from(
+        Transform::from_xyz(3.0, 1.0, -2.0)
+            .with_rotation(Quat::from_rotation_y(0.7))
+            .with_scale(Vec3::splat(2.0)),
+    );
+    let net = through_wire(&NetWorldTransform::from(world));
+
+    // Decomposing a rotated matrix isn't exact, so only the translation is checked exactly.
+    assert_eq!(net.translation, Vec3::new(3.0, 1.0, -2.0));
+    assert!(net.rotation.abs_diff_eq(Quat::from_rotation_y(0.7), 1e-5));
+    assert!(net.scale.abs_diff_eq(Vec3::splat(2.0), 1e-5));
+    assert!(GlobalTransform::from(net)
+        .affine()
+        .abs_diff_eq(world.affine(), 1e-5));
+}
+
+#[test]
+fn world_transform_to_local() {
+    let parent = GlobalTransform::from(Transform::from_xyz(1.0, 2.0, 3.0));
+    let world = NetWorldTransform::from(GlobalTransform::from(Transform::from_xyz(4.0, 4.0, 4.0)));
+
+    let local = world.to_local(Some(&parent));
+    assert!(local
+        .translation
+        .abs_diff_eq(Vec3::new(3.0, 2.0, 1.0), 1e-5));
+    assert_eq!(world.to_local(None), Transform::from_xyz(4.0, 4.0, 4.0));
+}
+
+#[test]
+fn color_srgb() {
+    round_trip::<Color, _>(NetColor::Srgb([255, 128, 0, 64]));
+    assert_eq!(
+        NetColor::from(Color::rgba(2.0, -1.0, 1.0, 0.0)),
+        NetColor::Srgb([255, 0, 255, 0])
+    );
+}
+
+#[test]
+fn color_linear() {
+    // Values above `1.0` are kept, as for emissive colors.
+    let color = Color::rgba_linear(4.0, 0.5, 0.0, 1.0);
+    let net = NetColor::linear(color);
+    assert_eq!(Color::from(net).as_linear_rgba_f32(), [4.0, 0.5, 0.0, 1.0]);
+    assert_eq!(NetColor::linear(Color::from(net)), net);
+    assert_eq!(through_wire(&net), net);
+
+    // Rounds to the nearest half, and saturates to infinity.
+    let [r, g, ..] =
+        Color::from(NetColor::linear(Color::rgba_linear(0.1, 1e6, 0.0, 1.0))).as_linear_rgba_f32();
+    assert!((r - 0.1).abs() < 1e-4);
+    assert_eq!(g, f32::INFINITY);
+}
+
+#[test]
+fn material() {
+    let material = StandardMaterial {
+        base_color: Color::rgb_u8(10, 20, 30),
+        emissive: Color::rgba_linear(2.0, 0.0, 0.0, 1.0),
+        perceptual_roughness: 0.25,
+        metallic: 1.0,
+        reflectance: 0.5,
+        alpha_mode: AlphaMode::Mask(0.5),
+        unlit: true,
+        ..default()
+    };
+    let net = NetMaterial::from(&material);
+    assert_eq!(NetMaterial::from(&StandardMaterial::from(net)), net);
+    assert_eq!(through_wire(&net), net);
+}